@@ -0,0 +1,101 @@
+//! Bidirectional text, per line.
+//!
+//! Text is stored in logical order, the order it is typed and read in. Lines
+//! with right-to-left characters are reordered for display with the Unicode
+//! bidi algorithm, treating each line as its own paragraph. Positions here are
+//! char indices.
+
+use serde::Deserialize;
+use unicode_bidi::BidiInfo;
+
+/// How Left and Right move the cursor through mixed-direction lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorMovement {
+    /// Step through the text in the order it is stored; on screen the cursor
+    /// jumps across direction changes.
+    #[default]
+    Logical,
+    /// Step to the neighbouring cell on screen.
+    Visual,
+}
+
+/// `order[visual] = logical` for each char of `line`, or `None` if the line
+/// reads left to right as it is.
+pub fn visual_order(line: &str) -> Option<Vec<usize>> {
+    let info = BidiInfo::new(line, None);
+    if !info.has_rtl() {
+        return None;
+    }
+    let para = info.paragraphs.first()?;
+    let levels = info.reordered_levels_per_char(para, 0..line.len());
+    Some(BidiInfo::reorder_visual(&levels))
+}
+
+/// The screen column of the char at logical index `x`.
+pub fn to_visual(line: &str, x: usize) -> usize {
+    match visual_order(line) {
+        Some(order) => order.iter().position(|&l| l == x).unwrap_or(x),
+        None => x,
+    }
+}
+
+/// Where the cursor lands moving one cell left or right on screen from `x`,
+/// as a logical index. `None` at the edge of the line or if it has no
+/// right-to-left text, where logical movement already is visual.
+pub fn visual_step(line: &str, x: usize, right: bool) -> Option<usize> {
+    let order = visual_order(line)?;
+    let len = order.len();
+    // Past the end of the line is past the right edge of the screen text.
+    let v = order.iter().position(|&l| l == x).unwrap_or(len);
+    let target = if right { v + 1 } else { v.checked_sub(1)? };
+    if target > len {
+        return None;
+    }
+    Some(order.get(target).copied().unwrap_or(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Latin, then a space and three Hebrew letters.
+    const MIXED: &str = "ab אבג";
+
+    #[test]
+    fn left_to_right_lines_are_not_reordered() {
+        assert_eq!(visual_order("plain text"), None);
+        assert_eq!(to_visual("plain text", 3), 3);
+        assert_eq!(visual_step("plain text", 3, true), None);
+    }
+
+    #[test]
+    fn right_to_left_runs_are_reversed() {
+        assert_eq!(visual_order(MIXED), Some(vec![0, 1, 2, 5, 4, 3]));
+        assert_eq!(visual_order("אבג"), Some(vec![2, 1, 0]));
+    }
+
+    #[test]
+    fn to_visual_finds_the_screen_column() {
+        assert_eq!(to_visual(MIXED, 1), 1);
+        assert_eq!(to_visual(MIXED, 3), 5);
+        assert_eq!(to_visual(MIXED, 5), 3);
+    }
+
+    #[test]
+    fn visual_step_follows_the_screen() {
+        // Right from the space lands on the leftmost Hebrew letter, the last
+        // one typed.
+        assert_eq!(visual_step(MIXED, 2, true), Some(5));
+        assert_eq!(visual_step(MIXED, 5, true), Some(4));
+        assert_eq!(visual_step(MIXED, 5, false), Some(2));
+        // Off the right edge is the end of the line.
+        assert_eq!(visual_step(MIXED, 3, true), Some(6));
+    }
+
+    #[test]
+    fn visual_step_stops_at_the_edges() {
+        assert_eq!(visual_step(MIXED, 0, false), None);
+        assert_eq!(visual_step(MIXED, 6, true), None);
+    }
+}
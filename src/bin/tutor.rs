@@ -0,0 +1,26 @@
+//! `tutor`, the same as `sanguine-tui tutor` under the name vimtutor users
+//! look for.
+//!
+//! The editor lives in the main binary rather than a library, so this runs
+//! that binary from the same directory instead of linking it in.
+
+use std::process::{Command, ExitCode};
+
+fn main() -> ExitCode {
+    let name = format!("sanguine-tui{}", std::env::consts::EXE_SUFFIX);
+    let editor = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(&name)));
+    let Some(editor) = editor else {
+        eprintln!("tutor: couldn't find {}", name);
+        return ExitCode::FAILURE;
+    };
+    match Command::new(&editor).arg("tutor").status() {
+        Ok(status) if status.success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("tutor: couldn't start {}: {}", editor.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
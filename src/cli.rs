@@ -0,0 +1,59 @@
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::PathBuf;
+
+/// A small terminal editor built on sanguine.
+#[derive(Debug, Parser)]
+#[command(
+    name = env!("CARGO_BIN_NAME"),
+    version,
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Without a subcommand, files are opened for editing.
+    #[command(flatten)]
+    pub edit: EditArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open files for editing.
+    Edit(EditArgs),
+    /// Show the line differences between two files.
+    Diff { before: PathBuf, after: PathBuf },
+    /// Open files read-only.
+    View { files: Vec<PathBuf> },
+    /// Learn the editor step by step, with exercises.
+    Tutor,
+    /// Work with the config file.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Work with key bindings.
+    #[command(subcommand)]
+    Keymap(KeymapCommand),
+    /// Print a completion script for `shell`.
+    Completions { shell: Shell },
+}
+
+#[derive(Debug, Args)]
+pub struct EditArgs {
+    /// Hand the files to an instance already running with `--reuse`, or
+    /// become that instance.
+    #[arg(long)]
+    pub reuse: bool,
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Load the config and key bindings and report any problems.
+    Check,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeymapCommand {
+    /// Print every binding in effect, as config.
+    Dump,
+}
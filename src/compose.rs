@@ -0,0 +1,201 @@
+//! Dead-key composition.
+//!
+//! Terminals only pass IME input on once it is finalized, but many send a
+//! dead key as a character of its own: `´` then `e` instead of `é`. The
+//! composer holds such keys as preedit text shown at the cursor and commits the
+//! composed character once a base key arrives.
+
+use sanguine::event::{KeyCode, KeyEvent, Modifiers};
+use unicode_normalization::UnicodeNormalization;
+
+/// Spacing forms of dead keys and the combining marks they stand for. Only
+/// non-ASCII forms are listed, so `^` and `~` still type themselves.
+const DEAD_KEYS: &[(char, char)] = &[
+    ('´', '\u{301}'),
+    ('¨', '\u{308}'),
+    ('ˆ', '\u{302}'),
+    ('˜', '\u{303}'),
+    ('ˇ', '\u{30c}'),
+    ('¸', '\u{327}'),
+    ('˚', '\u{30a}'),
+    ('˘', '\u{306}'),
+    ('˝', '\u{30b}'),
+    ('˛', '\u{328}'),
+    ('¯', '\u{304}'),
+];
+
+/// The combining mark `c` stands for, if it is a dead key. Bare combining
+/// marks count as their own dead key.
+fn combining(c: char) -> Option<char> {
+    match c {
+        '\u{300}'..='\u{36f}' => Some(c),
+        _ => DEAD_KEYS.iter().find(|(k, _)| *k == c).map(|(_, m)| *m),
+    }
+}
+
+fn char_key(c: char) -> KeyEvent {
+    KeyEvent {
+        key: KeyCode::Char(c),
+        modifiers: Modifiers::NONE,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Composer {
+    /// Dead keys typed so far, as received.
+    dead: Vec<char>,
+}
+
+impl Composer {
+    pub fn composing(&self) -> bool {
+        !self.dead.is_empty()
+    }
+
+    /// What to draw at the cursor while composing.
+    pub fn preedit(&self) -> String {
+        self.dead
+            .iter()
+            .flat_map(|&c| match c {
+                // A bare mark needs something to sit on.
+                '\u{300}'..='\u{36f}' => vec!['◌', c],
+                _ => vec![c],
+            })
+            .collect()
+    }
+
+    /// Feed in a key and get back the keys to type, in order.
+    pub fn handle(&mut self, key: KeyEvent) -> Vec<KeyEvent> {
+        let plain = !key.modifiers.intersects(Modifiers::CTRL | Modifiers::ALT);
+        match key.key {
+            KeyCode::Char(c) if plain && combining(c).is_some() => {
+                self.dead.push(c);
+                vec![]
+            }
+            _ if self.dead.is_empty() => vec![key],
+            KeyCode::Escape => {
+                self.dead.clear();
+                vec![]
+            }
+            KeyCode::Backspace => {
+                self.dead.pop();
+                vec![]
+            }
+            // Space types the dead keys themselves.
+            KeyCode::Char(' ') if plain => self.flush(),
+            KeyCode::Char(c) if plain => {
+                let marks = self.dead.iter().filter_map(|&d| combining(d));
+                let composed: Vec<char> = std::iter::once(c).chain(marks).nfc().collect();
+                if let [single] = composed[..] {
+                    self.dead.clear();
+                    vec![char_key(single)]
+                } else {
+                    // Nothing precomposed exists; type it all as it came.
+                    let mut keys = self.flush();
+                    keys.push(key);
+                    keys
+                }
+            }
+            _ => {
+                let mut keys = self.flush();
+                keys.push(key);
+                keys
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Vec<KeyEvent> {
+        self.dead.drain(..).map(char_key).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `keys` in and collect the characters typed.
+    fn typed(composer: &mut Composer, keys: &str) -> String {
+        keys.chars()
+            .flat_map(|c| composer.handle(char_key(c)))
+            .map(|k| match k.key {
+                KeyCode::Char(c) => c,
+                other => panic!("typed {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plain_keys_pass_through() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "e^~"), "e^~");
+        assert!(!composer.composing());
+    }
+
+    #[test]
+    fn dead_key_composes_with_the_next_letter() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "´"), "");
+        assert!(composer.composing());
+        assert_eq!(composer.preedit(), "´");
+        assert_eq!(typed(&mut composer, "e"), "é");
+        assert!(!composer.composing());
+    }
+
+    #[test]
+    fn dead_keys_stack() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "¨´u"), "ǘ");
+    }
+
+    #[test]
+    fn bare_marks_are_dead_keys() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "\u{301}"), "");
+        assert_eq!(composer.preedit(), "◌\u{301}");
+        assert_eq!(typed(&mut composer, "a"), "á");
+    }
+
+    #[test]
+    fn uncomposable_keys_type_as_they_came() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "´q"), "´q");
+        assert!(!composer.composing());
+    }
+
+    #[test]
+    fn space_types_the_dead_key() {
+        let mut composer = Composer::default();
+        assert_eq!(typed(&mut composer, "¨ "), "¨");
+    }
+
+    #[test]
+    fn escape_and_backspace_edit_the_preedit() {
+        let mut composer = Composer::default();
+        typed(&mut composer, "¨´");
+        let backspace = KeyEvent {
+            key: KeyCode::Backspace,
+            modifiers: Modifiers::NONE,
+        };
+        assert!(composer.handle(backspace).is_empty());
+        assert_eq!(composer.preedit(), "¨");
+        let escape = KeyEvent {
+            key: KeyCode::Escape,
+            modifiers: Modifiers::NONE,
+        };
+        assert!(composer.handle(escape).is_empty());
+        assert!(!composer.composing());
+    }
+
+    #[test]
+    fn shortcuts_flush_the_dead_keys_first() {
+        let mut composer = Composer::default();
+        typed(&mut composer, "´");
+        let save = KeyEvent {
+            key: KeyCode::Char('s'),
+            modifiers: Modifiers::CTRL,
+        };
+        let keys = composer.handle(save);
+        let codes: Vec<KeyCode> = keys.iter().map(|k| k.key).collect();
+        assert_eq!(codes, vec![KeyCode::Char('´'), KeyCode::Char('s')]);
+        assert_eq!(keys[1].modifiers, Modifiers::CTRL);
+    }
+}
@@ -0,0 +1,432 @@
+//! What to do when a file with unsaved edits changes on disk.
+//!
+//! [`ConflictPanel`] shows a word-level diff of the buffer against the file as
+//! it now is, and resolves the conflict by keeping the buffer, taking the file,
+//! or merging the two with [`merge`].
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    diff::{diff, Change},
+    theme::{Role, Theme},
+    Message,
+};
+
+pub enum Resolution {
+    /// Keep the buffer as it is; saving it overwrites the file.
+    Keep,
+    /// Replace the buffer with the file.
+    Take,
+    /// Replace the buffer with both sets of changes merged.
+    Merge(String),
+}
+
+/// Split `text` into words, runs of spaces, and single newlines and
+/// punctuation, so that a diff of them reads like one of prose.
+fn words(text: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c == '\n' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else if c.is_alphanumeric() || c == '_' {
+            2
+        } else {
+            3
+        }
+    };
+    let mut words = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let joins = matches!(class(c), 1 | 2);
+        while let Some(&(_, next)) = chars.peek() {
+            if !joins || class(next) != class(c) {
+                break;
+            }
+            chars.next();
+        }
+        let end = chars.peek().map_or(text.len(), |&(end, _)| end);
+        words.push(&text[start..end]);
+    }
+    words
+}
+
+/// The word diff of `a` against `b`, one entry per line.
+fn word_diff(a: &str, b: &str) -> Vec<Vec<Change<String>>> {
+    let (a, b) = (words(a), words(b));
+    // Most of a file is usually untouched, and the diff is quadratic.
+    let prefix = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let changes = a[..prefix]
+        .iter()
+        .map(Change::Same)
+        .chain(diff(
+            &a[prefix..a.len() - suffix],
+            &b[prefix..b.len() - suffix],
+        ))
+        .chain(a[a.len() - suffix..].iter().map(Change::Same));
+    let mut lines = vec![vec![]];
+    for change in changes {
+        let line = lines.last_mut().unwrap();
+        match change {
+            Change::Same(&"\n") => {}
+            Change::Same(w) => line.push(Change::Same(w.to_string())),
+            // A changed line break still shows where it was.
+            Change::Removed(&"\n") => line.push(Change::Removed("⏎".to_owned())),
+            Change::Removed(w) => line.push(Change::Removed(w.to_string())),
+            Change::Added(&"\n") => line.push(Change::Added("⏎".to_owned())),
+            Change::Added(w) => line.push(Change::Added(w.to_string())),
+        }
+        if let Change::Same(&"\n") | Change::Removed(&"\n") | Change::Added(&"\n") = change {
+            lines.push(vec![]);
+        }
+    }
+    lines
+}
+
+/// Lines `start..end` of the base, replaced with `lines`.
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+fn hunks<'a>(base: &[&'a str], side: &[&'a str]) -> Vec<Hunk<'a>> {
+    let mut hunks = vec![];
+    let mut current: Option<Hunk> = None;
+    let mut i = 0;
+    for change in diff(base, side) {
+        match change {
+            Change::Same(_) => {
+                hunks.extend(current.take());
+                i += 1;
+            }
+            Change::Removed(_) => {
+                current
+                    .get_or_insert(Hunk {
+                        start: i,
+                        end: i,
+                        lines: vec![],
+                    })
+                    .end = i + 1;
+                i += 1;
+            }
+            Change::Added(line) => current
+                .get_or_insert(Hunk {
+                    start: i,
+                    end: i,
+                    lines: vec![],
+                })
+                .lines
+                .push(*line),
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// `base[start..end]` with `hunks`, which must lie inside it, applied.
+fn apply<'a>(base: &[&'a str], start: usize, end: usize, hunks: &[Hunk<'a>]) -> Vec<&'a str> {
+    let mut out = vec![];
+    let mut at = start;
+    for hunk in hunks {
+        out.extend(&base[at..hunk.start]);
+        out.extend(&hunk.lines);
+        at = hunk.end;
+    }
+    out.extend(&base[at..end]);
+    out
+}
+
+/// Merge the edits `mine` and `theirs` each made to `base`, line by line.
+///
+/// Where both sides changed the same lines differently both versions are kept,
+/// between conflict markers. Returns the merged text and how many conflicts
+/// it has.
+pub fn merge(base: &str, mine: &str, theirs: &str) -> (String, usize) {
+    let base: Vec<&str> = base.lines().collect();
+    let mine: Vec<&str> = mine.lines().collect();
+    let theirs: Vec<&str> = theirs.lines().collect();
+    let mut ours = hunks(&base, &mine).into_iter().peekable();
+    let mut others = hunks(&base, &theirs).into_iter().peekable();
+    let mut out: Vec<&str> = vec![];
+    let mut conflicts = 0;
+    let mut at = 0;
+    loop {
+        let start = match (ours.peek(), others.peek()) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(h), None) | (None, Some(h)) => h.start,
+            (None, None) => break,
+        };
+        // Gather every hunk touching the lines the group covers so far.
+        let (mut a, mut b) = (vec![], vec![]);
+        let mut end = start;
+        loop {
+            if let Some(hunk) = ours.next_if(|h| h.start <= end) {
+                end = end.max(hunk.end);
+                a.push(hunk);
+            } else if let Some(hunk) = others.next_if(|h| h.start <= end) {
+                end = end.max(hunk.end);
+                b.push(hunk);
+            } else {
+                break;
+            }
+        }
+        out.extend(&base[at..start]);
+        let (mine, theirs) = (apply(&base, start, end, &a), apply(&base, start, end, &b));
+        if a.is_empty() || mine == theirs {
+            out.extend(theirs);
+        } else if b.is_empty() {
+            out.extend(mine);
+        } else {
+            conflicts += 1;
+            out.push("<<<<<<< buffer");
+            out.extend(mine);
+            out.push("=======");
+            out.extend(theirs);
+            out.push(">>>>>>> disk");
+        }
+        at = end;
+    }
+    out.extend(&base[at..]);
+    (out.join("\n"), conflicts)
+}
+
+/// A floating word diff of a buffer against the changed file on disk.
+pub struct ConflictPanel {
+    file: PathBuf,
+    lines: Vec<Vec<Change<String>>>,
+    theirs: String,
+    merged: (String, usize),
+    theme: Arc<Theme>,
+    scroll: usize,
+}
+
+impl ConflictPanel {
+    /// `base` is the text `mine` and `theirs` both started from.
+    pub fn new(
+        file: PathBuf,
+        base: &str,
+        mine: &str,
+        theirs: String,
+        theme: Arc<Theme>,
+    ) -> ConflictPanel {
+        let lines = word_diff(mine, &theirs);
+        // Open on the first change, with a line of context.
+        let scroll = lines
+            .iter()
+            .position(|line| line.iter().any(|c| !matches!(c, Change::Same(_))))
+            .unwrap_or(0)
+            .saturating_sub(1);
+        ConflictPanel {
+            file,
+            merged: merge(base, mine, &theirs),
+            lines,
+            theirs,
+            theme,
+            scroll,
+        }
+    }
+
+    fn resolve(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, resolution: Resolution) {
+        cx.tx
+            .send(UserEvent::User(Message::Resolve {
+                file: self.file.clone(),
+                theirs: std::mem::take(&mut self.theirs),
+                resolution,
+            }))
+            .ok();
+        cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+    }
+}
+
+impl Widget<Message, ()> for ConflictPanel {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let removed = self.theme.get(Role::DiffRemoved);
+        let added = self.theme.get(Role::DiffAdded);
+        let lines: Vec<Spans> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .map(|line| {
+                Spans::from(
+                    line.iter()
+                        .map(|change| match change {
+                            Change::Same(w) => Span::raw(w.as_str()),
+                            Change::Removed(w) => Span::styled(
+                                w.as_str(),
+                                Style::default()
+                                    .fg(removed.color.tui())
+                                    .add_modifier(Modifier::CROSSED_OUT),
+                            ),
+                            Change::Added(w) => Span::styled(
+                                w.as_str(),
+                                Style::default()
+                                    .fg(added.color.tui())
+                                    .add_modifier(Modifier::UNDERLINED),
+                            ),
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        let name = self.file.file_name().unwrap_or_default().to_string_lossy();
+        let title = format!(
+            "{} changed on disk: [k]eep buffer, [t]ake file, [m]erge ({} conflicts)",
+            name, self.merged.1
+        );
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default().borders(Borders::ALL).title(title);
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let last = self.lines.len().saturating_sub(1);
+        match event {
+            Event::Key(k) if k.key == KeyCode::Escape || k.key == KeyCode::Char('k') => {
+                self.resolve(cx, Resolution::Keep);
+            }
+            Event::Key(k) if k.key == KeyCode::Char('t') => self.resolve(cx, Resolution::Take),
+            Event::Key(k) if k.key == KeyCode::Char('m') => {
+                let merged = std::mem::take(&mut self.merged.0);
+                self.resolve(cx, Resolution::Merge(merged));
+            }
+            Event::Key(k) if k.key == KeyCode::DownArrow || k.key == KeyCode::Char('j') => {
+                self.scroll = (self.scroll + 1).min(last);
+            }
+            Event::Key(k) if k.key == KeyCode::UpArrow => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Event::Key(k) if k.key == KeyCode::PageDown => {
+                self.scroll = (self.scroll + 20).min(last);
+            }
+            Event::Key(k) if k.key == KeyCode::PageUp => {
+                self.scroll = self.scroll.saturating_sub(20);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(w: &str) -> Change<String> {
+        Change::Same(w.to_owned())
+    }
+
+    fn removed(w: &str) -> Change<String> {
+        Change::Removed(w.to_owned())
+    }
+
+    fn added(w: &str) -> Change<String> {
+        Change::Added(w.to_owned())
+    }
+
+    #[test]
+    fn words_split_like_prose() {
+        assert_eq!(
+            words("foo  bar, baz!!\n"),
+            vec!["foo", "  ", "bar", ",", " ", "baz", "!", "!", "\n"]
+        );
+    }
+
+    #[test]
+    fn word_diff_marks_changed_words() {
+        assert_eq!(
+            word_diff("the cat sat", "the dog sat"),
+            vec![vec![
+                same("the"),
+                same(" "),
+                removed("cat"),
+                added("dog"),
+                same(" "),
+                same("sat"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn word_diff_splits_lines() {
+        assert_eq!(
+            word_diff("a\nb", "a\nc"),
+            vec![vec![same("a")], vec![removed("b"), added("c")]]
+        );
+    }
+
+    #[test]
+    fn word_diff_shows_changed_line_breaks() {
+        assert_eq!(
+            word_diff("a b", "a\nb"),
+            vec![vec![same("a"), removed(" "), added("⏎")], vec![same("b")]]
+        );
+    }
+
+    #[test]
+    fn merge_takes_edits_to_different_lines() {
+        assert_eq!(
+            merge("a\nb\nc", "A\nb\nc", "a\nb\nC"),
+            ("A\nb\nC".to_owned(), 0)
+        );
+    }
+
+    #[test]
+    fn merge_takes_the_same_edit_once() {
+        assert_eq!(merge("a\nb", "a\nB", "a\nB"), ("a\nB".to_owned(), 0));
+    }
+
+    #[test]
+    fn merge_keeps_additions_from_both_sides() {
+        assert_eq!(
+            merge("a\nb\nc", "x\na\nb\nc", "a\nb\nc\ny"),
+            ("x\na\nb\nc\ny".to_owned(), 0)
+        );
+    }
+
+    #[test]
+    fn merge_marks_conflicting_edits() {
+        assert_eq!(
+            merge("a\nb\nc", "a\nmine\nc", "a\ntheirs\nc"),
+            (
+                "a\n<<<<<<< buffer\nmine\n=======\ntheirs\n>>>>>>> disk\nc".to_owned(),
+                1
+            )
+        );
+    }
+}
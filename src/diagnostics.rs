@@ -0,0 +1,247 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use termwiz::cell::Underline;
+
+use crate::{
+    error::{DemoResult, Poisoned},
+    fs,
+    selection::Selection,
+    theme::{Role, Theme},
+    Message,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+impl Severity {
+    pub fn role(self) -> Role {
+        match self {
+            Severity::Error => Role::Error,
+            Severity::Warning => Role::Warning,
+            Severity::Info => Role::Info,
+            Severity::Hint => Role::Hint,
+        }
+    }
+
+    /// A different shape for each severity, so they tell apart without color.
+    pub fn underline(self) -> Underline {
+        match self {
+            Severity::Error => Underline::Curly,
+            Severity::Warning => Underline::Dashed,
+            Severity::Info => Underline::Dotted,
+            Severity::Hint => Underline::Single,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Severity> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" | "fatal error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "info" | "note" => Some(Severity::Info),
+            "hint" | "help" => Some(Severity::Hint),
+            _ => None,
+        }
+    }
+}
+
+/// A message attached to a range of a file. Positions are zero-based
+/// `(line, column)` pairs and `end` is exclusive.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// What produced the diagnostic, e.g. `"make"` or `"rust-analyzer"`.
+    pub source: String,
+    pub severity: Severity,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub message: String,
+}
+
+/// Every diagnostic known to the editor, grouped by file.
+///
+/// Producers own their diagnostics by `source` and replace them wholesale, so
+/// the LSP demo, the quickfix parser and plugins don't clobber each other.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    files: BTreeMap<PathBuf, Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    /// Files go by the name buffers use for them, so Goto from the panel
+    /// lands in the tab already open for the file.
+    fn key(path: &Path) -> PathBuf {
+        fs::normalize(path)
+    }
+
+    /// Replace everything previously reported by `source`.
+    pub fn replace_source(
+        &mut self,
+        source: &str,
+        diagnostics: impl IntoIterator<Item = (PathBuf, Diagnostic)>,
+    ) {
+        for diags in self.files.values_mut() {
+            diags.retain(|d| d.source != source);
+        }
+        for (path, diag) in diagnostics {
+            self.files.entry(Self::key(&path)).or_default().push(diag);
+        }
+        self.files.retain(|_, diags| !diags.is_empty());
+        for diags in self.files.values_mut() {
+            diags.sort_by_key(|d| (d.start, d.severity));
+        }
+    }
+
+    pub fn for_file(&self, path: &Path) -> &[Diagnostic] {
+        self.files
+            .get(&Self::key(path))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &Diagnostic)> {
+        self.files
+            .iter()
+            .flat_map(|(path, diags)| diags.iter().map(move |d| (path.as_path(), d)))
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.iter().filter(|(_, d)| d.severity == severity).count()
+    }
+}
+
+/// A floating list of every diagnostic. Enter jumps to the selected one.
+pub struct DiagnosticsPanel {
+    diagnostics: Arc<RwLock<Diagnostics>>,
+    theme: Arc<Theme>,
+    selection: Selection<ListState>,
+}
+
+impl DiagnosticsPanel {
+    pub fn new(diagnostics: Arc<RwLock<Diagnostics>>, theme: Arc<Theme>) -> DiagnosticsPanel {
+        DiagnosticsPanel {
+            diagnostics,
+            theme,
+            selection: Selection::default(),
+        }
+    }
+
+    /// Show the selected diagnostic in the preview pane.
+    fn preview(&self, cx: &mut UpdateCtx<'_, Message, ()>) -> DemoResult<()> {
+        let target = self
+            .diagnostics
+            .read()
+            .or_poisoned("diagnostics")?
+            .iter()
+            .nth(self.selection.index())
+            .map(|(path, d)| (path.to_path_buf(), d.start.0));
+        cx.tx.send(UserEvent::User(Message::Preview(target))).ok();
+        Ok(())
+    }
+}
+
+impl Widget<Message, ()> for DiagnosticsPanel {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        // Poisoned by a panic elsewhere: skip the frame instead of panicking
+        // again on every one.
+        let Ok(diagnostics) = self.diagnostics.read() else {
+            return None;
+        };
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let items: Vec<ListItem> = diagnostics
+            .iter()
+            .map(|(path, d)| {
+                let path = path.strip_prefix(&cwd).unwrap_or(path);
+                ListItem::new(Spans::from(vec![
+                    Span::styled(
+                        format!("{} ", self.theme.get(d.severity.role()).glyph),
+                        Style::default().fg(self.theme.get(d.severity.role()).color.tui()),
+                    ),
+                    Span::raw(format!(
+                        "{}:{}:{} ",
+                        path.display(),
+                        d.start.0 + 1,
+                        d.start.1 + 1
+                    )),
+                    Span::raw(d.message.as_str()),
+                ]))
+            })
+            .collect();
+        let len = items.len();
+        let title = format!("Diagnostics ({})", len);
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let list = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                let area = f.size();
+                self.selection.render(f, list, area, len);
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let len = self
+            .diagnostics
+            .read()
+            .or_poisoned("diagnostics")?
+            .iter()
+            .count();
+        match event {
+            Event::Key(k) if k.key == KeyCode::Escape || k.key == KeyCode::Char('q') => {
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            Event::Key(k) if k.key == KeyCode::Enter => {
+                let target = self
+                    .diagnostics
+                    .read()
+                    .or_poisoned("diagnostics")?
+                    .iter()
+                    .nth(self.selection.index())
+                    .map(|(path, d)| (path.to_path_buf(), d.start));
+                if let Some((path, (line, col))) = target {
+                    cx.tx
+                        .send(UserEvent::User(Message::Goto(path, line, col)))
+                        .ok();
+                    cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+                }
+            }
+            Event::Key(k) => {
+                if self.selection.handle(k.key, len) {
+                    self.preview(cx)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use ratatui::{
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    theme::{Role, Theme},
+    Message,
+};
+
+/// Past this many cells the LCS table gets too big, and everything is shown
+/// as replaced instead.
+const MAX_TABLE: usize = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<T> {
+    Same(T),
+    Removed(T),
+    Added(T),
+}
+
+/// The shortest edit turning `a` into `b`, by longest common subsequence.
+pub fn diff<'a, T: PartialEq>(a: &'a [T], b: &'a [T]) -> Vec<Change<&'a T>> {
+    if a.len().saturating_mul(b.len()) > MAX_TABLE {
+        return a
+            .iter()
+            .map(Change::Removed)
+            .chain(b.iter().map(Change::Added))
+            .collect();
+    }
+    // lcs[i][j] is the LCS length of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            changes.push(Change::Same(&a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(Change::Removed(&a[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(&b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(a[i..].iter().map(Change::Removed));
+    changes.extend(b[j..].iter().map(Change::Added));
+    changes
+}
+
+/// A scrollable line diff of two texts.
+pub struct DiffView {
+    title: String,
+    lines: Vec<Change<String>>,
+    theme: Arc<Theme>,
+    scroll: usize,
+}
+
+impl DiffView {
+    pub fn new(title: String, before: &str, after: &str, theme: Arc<Theme>) -> DiffView {
+        let before: Vec<&str> = before.lines().collect();
+        let after: Vec<&str> = after.lines().collect();
+        let lines = diff(&before, &after)
+            .into_iter()
+            .map(|change| match change {
+                Change::Same(l) => Change::Same(l.to_string()),
+                Change::Removed(l) => Change::Removed(l.to_string()),
+                Change::Added(l) => Change::Added(l.to_string()),
+            })
+            .collect();
+        DiffView {
+            title,
+            lines,
+            theme,
+            scroll: 0,
+        }
+    }
+}
+
+impl Widget<Message, ()> for DiffView {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let styled = |role: Role, line: &str| {
+            let highlight = self.theme.get(role);
+            Spans::from(Span::styled(
+                format!("{} {}", highlight.glyph, line),
+                Style::default().fg(highlight.color.tui()),
+            ))
+        };
+        let lines: Vec<Spans> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .map(|change| match change {
+                Change::Same(l) => Spans::from(format!("  {}", l)),
+                Change::Removed(l) => styled(Role::DiffRemoved, l),
+                Change::Added(l) => styled(Role::DiffAdded, l),
+            })
+            .collect();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str());
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        _cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let last = self.lines.len().saturating_sub(1);
+        match event {
+            Event::Key(k) if k.key == KeyCode::DownArrow || k.key == KeyCode::Char('j') => {
+                self.scroll = (self.scroll + 1).min(last);
+            }
+            Event::Key(k) if k.key == KeyCode::UpArrow || k.key == KeyCode::Char('k') => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Event::Key(k) if k.key == KeyCode::PageDown => {
+                self.scroll = (self.scroll + 20).min(last);
+            }
+            Event::Key(k) if k.key == KeyCode::PageUp => {
+                self.scroll = self.scroll.saturating_sub(20);
+            }
+            // Jump to the next change.
+            Event::Key(k) if k.key == KeyCode::Char('n') => {
+                let next = self
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .skip(self.scroll + 1)
+                    .find(|(i, c)| {
+                        !matches!(c, Change::Same(_))
+                            && matches!(self.lines.get(i - 1), Some(Change::Same(_)))
+                    });
+                if let Some((i, _)) = next {
+                    self.scroll = i;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
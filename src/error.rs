@@ -0,0 +1,192 @@
+//! Errors that say what went wrong and what was being done at the time.
+//!
+//! sanguine's `Error::external` keeps only a message. [`DemoError`] keeps the
+//! kind of failure, so a typo in the config reads differently from a full
+//! disk, and the steps that led to it, like `saving src/main.rs`. It becomes a
+//! sanguine error where a widget hands it back to the app.
+
+use sanguine::error::Error;
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::PoisonError,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::session::Session;
+
+pub type DemoResult<T> = Result<T, DemoError>;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    /// A thread panicked while holding the lock on this, so it may be left
+    /// half-updated.
+    Poisoned(&'static str),
+    /// A panic, with where it happened.
+    Panicked(String),
+    /// A setting, keymap or theme that can't be used.
+    Config(String),
+    /// An external program such as a formatter failed.
+    Tool {
+        command: String,
+        message: String,
+    },
+    /// Asked for something that isn't allowed right now, like editing a
+    /// read-only buffer.
+    Refused(String),
+}
+
+#[derive(Debug)]
+pub struct DemoError {
+    pub kind: ErrorKind,
+    /// What was being done, outermost last.
+    context: Vec<String>,
+}
+
+impl DemoError {
+    fn new(kind: ErrorKind) -> DemoError {
+        DemoError {
+            kind,
+            context: vec![],
+        }
+    }
+
+    pub fn poisoned(what: &'static str) -> DemoError {
+        DemoError::new(ErrorKind::Poisoned(what))
+    }
+
+    pub fn panicked(message: impl Into<String>) -> DemoError {
+        DemoError::new(ErrorKind::Panicked(message.into()))
+    }
+
+    pub fn config(message: impl Into<String>) -> DemoError {
+        DemoError::new(ErrorKind::Config(message.into()))
+    }
+
+    pub fn tool(command: impl Into<String>, message: impl fmt::Display) -> DemoError {
+        DemoError::new(ErrorKind::Tool {
+            command: command.into(),
+            message: message.to_string(),
+        })
+    }
+
+    pub fn refused(message: impl Into<String>) -> DemoError {
+        DemoError::new(ErrorKind::Refused(message.into()))
+    }
+
+    /// Note that the error happened while doing `context`.
+    pub fn context(mut self, context: impl Into<String>) -> DemoError {
+        self.context.push(context.into());
+        self
+    }
+
+    /// Whether carrying on is safe. After a poisoned lock the state it
+    /// guarded can't be trusted.
+    pub fn recoverable(&self) -> bool {
+        !matches!(self.kind, ErrorKind::Poisoned(_))
+    }
+}
+
+impl fmt::Display for DemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for context in self.context.iter().rev() {
+            write!(f, "{}: ", context)?;
+        }
+        match &self.kind {
+            ErrorKind::Io(e) => write!(f, "{}", e),
+            ErrorKind::Poisoned(what) => write!(f, "{} was left broken by a crash", what),
+            ErrorKind::Panicked(message) => write!(f, "panicked: {}", message),
+            ErrorKind::Config(message) | ErrorKind::Refused(message) => write!(f, "{}", message),
+            ErrorKind::Tool { command, message } => write!(f, "{}: {}", command, message),
+        }
+    }
+}
+
+impl std::error::Error for DemoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DemoError {
+    fn from(e: io::Error) -> DemoError {
+        DemoError::new(ErrorKind::Io(e))
+    }
+}
+
+impl From<DemoError> for Error {
+    fn from(e: DemoError) -> Error {
+        Error::external(e)
+    }
+}
+
+/// [`DemoError::context`] for results.
+pub trait Context<T> {
+    fn context(self, context: impl Into<String>) -> DemoResult<T>;
+}
+
+impl<T, E: Into<DemoError>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> DemoResult<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+/// Results of taking a lock, which fail only if it was poisoned.
+pub trait Poisoned<T> {
+    fn or_poisoned(self, what: &'static str) -> DemoResult<T>;
+}
+
+impl<T, G> Poisoned<T> for Result<T, PoisonError<G>> {
+    fn or_poisoned(self, what: &'static str) -> DemoResult<T> {
+        self.map_err(|_| DemoError::poisoned(what))
+    }
+}
+
+/// Append `error` to `errors.log` next to the session file, for what the
+/// status bar is too small to keep.
+pub fn log(error: &DemoError) {
+    let Some(path) = Session::path().map(|p| p.with_file_name("errors.log")) else {
+        return;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let kind = match error.kind {
+        ErrorKind::Io(_) => "io",
+        ErrorKind::Poisoned(_) => "poisoned",
+        ErrorKind::Panicked(_) => "panic",
+        ErrorKind::Config(_) => "config",
+        ErrorKind::Tool { .. } => "tool",
+        ErrorKind::Refused(_) => "refused",
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+        })
+        .and_then(|mut file| writeln!(file, "{} [{}] {}", now, kind, error));
+    // Nowhere left to report it.
+    written.ok();
+}
+
+/// Log `error` and word it for the status bar.
+pub fn report(error: &DemoError) -> String {
+    if let ErrorKind::Refused(_) = error.kind {
+        return error.to_string();
+    }
+    log(error);
+    if error.recoverable() {
+        error.to_string()
+    } else {
+        format!("{}; save your work and restart", error)
+    }
+}
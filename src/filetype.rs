@@ -0,0 +1,21 @@
+use std::path::Path;
+
+/// Guess a buffer's filetype from its extension.
+pub fn detect(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?;
+    Some(match ext {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "json" => "json",
+        "css" | "scss" => "css",
+        "html" | "htm" => "html",
+        "md" | "markdown" => "markdown",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "c" | "h" => "c",
+        "go" => "go",
+        _ => return None,
+    })
+}
@@ -0,0 +1,103 @@
+//! The terminal's own focus, through xterm's focus reporting.
+//!
+//! While reporting is on, the terminal sends `CSI I` when its window gains
+//! focus and `CSI O` when it loses it. termwiz doesn't decode these, so they
+//! reach the editor as keys: alt+`[`, then `I` or `O`. [`FocusReports`] picks
+//! them back out of the key stream.
+
+use sanguine::event::{KeyCode, KeyEvent, Modifiers};
+use std::io::{self, Write};
+
+fn write(escape: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(escape.as_bytes())?;
+    out.flush()
+}
+
+/// Turns focus reporting on while it lives.
+pub struct ReportingGuard(());
+
+impl ReportingGuard {
+    pub fn enable() -> ReportingGuard {
+        write("\x1b[?1004h").ok();
+        ReportingGuard(())
+    }
+}
+
+impl Drop for ReportingGuard {
+    fn drop(&mut self) {
+        write("\x1b[?1004l").ok();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Gained,
+    Lost,
+}
+
+fn introducer() -> KeyEvent {
+    KeyEvent {
+        key: KeyCode::Char('['),
+        modifiers: Modifiers::ALT,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FocusReports {
+    /// Saw alt+`[`, which may start a report.
+    started: bool,
+}
+
+impl FocusReports {
+    /// Feed in a key and get back the keys to handle as usual, along with the
+    /// focus change they reported, if any. A real alt+`[` is held back until
+    /// the next key shows it isn't a report.
+    pub fn handle(&mut self, key: KeyEvent) -> (Vec<KeyEvent>, Option<Focus>) {
+        let plain = !key.modifiers.intersects(Modifiers::CTRL | Modifiers::ALT);
+        if std::mem::take(&mut self.started) {
+            return match key.key {
+                KeyCode::Char('I') if plain => (vec![], Some(Focus::Gained)),
+                KeyCode::Char('O') if plain => (vec![], Some(Focus::Lost)),
+                _ => (vec![introducer(), key], None),
+            };
+        }
+        if key == introducer() {
+            self.started = true;
+            return (vec![], None);
+        }
+        (vec![key], None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn char_key(c: char) -> KeyEvent {
+        KeyEvent {
+            key: KeyCode::Char(c),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn reports_are_taken_out_of_the_keys() {
+        let mut reports = FocusReports::default();
+        assert_eq!(reports.handle(introducer()), (vec![], None));
+        assert_eq!(reports.handle(char_key('O')), (vec![], Some(Focus::Lost)));
+        assert_eq!(reports.handle(introducer()), (vec![], None));
+        assert_eq!(reports.handle(char_key('I')), (vec![], Some(Focus::Gained)));
+    }
+
+    #[test]
+    fn other_keys_pass_through() {
+        let mut reports = FocusReports::default();
+        assert_eq!(reports.handle(char_key('O')), (vec![char_key('O')], None));
+        reports.handle(introducer());
+        assert_eq!(
+            reports.handle(char_key('x')),
+            (vec![introducer(), char_key('x')], None)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Deserialize;
+use std::{path::Path, process::Stdio};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::error::{DemoError, DemoResult};
+
+/// An external program that reads a buffer on stdin and writes the formatted
+/// buffer to stdout.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Formatter {
+    pub command: String,
+    /// Arguments passed to `command`. `{path}` is replaced with the buffer's path.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Formatter {
+    fn new(command: &str, args: &[&str]) -> Formatter {
+        Formatter {
+            command: command.to_owned(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// The formatter used for `filetype` when the config doesn't name one.
+    pub fn builtin(filetype: &str) -> Option<Formatter> {
+        match filetype {
+            "rust" => Some(Formatter::new("rustfmt", &["--edition", "2021"])),
+            "python" => Some(Formatter::new("black", &["--quiet", "-"])),
+            "javascript" | "typescript" | "json" | "css" | "html" | "markdown" | "yaml" => {
+                Some(Formatter::new("prettier", &["--stdin-filepath", "{path}"]))
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn run(&self, path: &Path, input: String) -> DemoResult<String> {
+        let path = path.to_string_lossy();
+        let mut child = Command::new(&self.command)
+            .args(self.args.iter().map(|a| a.replace("{path}", &path)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| DemoError::tool(&self.command, e))?;
+
+        // Feed stdin from another task so a formatter that starts writing
+        // before it has read everything can't deadlock us.
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| DemoError::tool(&self.command, "stdin is not piped"))?;
+        let writer = tokio::spawn(async move { stdin.write_all(input.as_bytes()).await });
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| DemoError::tool(&self.command, e))?;
+        let written = writer
+            .await
+            .map_err(|e| DemoError::tool(&self.command, e))?;
+
+        // A formatter that gave up early also broke the pipe, and its stderr
+        // says why far better than the write error does.
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .unwrap_or("no output");
+            return Err(DemoError::tool(
+                &self.command,
+                format!("failed ({}): {}", output.status, reason),
+            ));
+        }
+        written.map_err(|e| DemoError::tool(&self.command, e))?;
+        String::from_utf8(output.stdout)
+            .map_err(|_| DemoError::tool(&self.command, "output is not utf-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(formatter: &Formatter, input: &str) -> DemoResult<String> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(formatter.run(Path::new("test.txt"), input.to_owned()))
+    }
+
+    #[test]
+    fn output_replaces_the_input() {
+        let formatter = Formatter::new("tr", &["a-z", "A-Z"]);
+        assert_eq!(run(&formatter, "fn main() {}\n").unwrap(), "FN MAIN() {}\n");
+    }
+
+    #[test]
+    fn missing_command_is_an_error() {
+        let formatter = Formatter::new("sanguine-no-such-formatter", &[]);
+        assert!(run(&formatter, "").is_err());
+    }
+
+    #[test]
+    fn early_exit_reports_stderr() {
+        // Exits without reading, so the write below would break the pipe.
+        let formatter = Formatter::new("sh", &["-c", "echo 'bad input' >&2; exit 3"]);
+        let input = "x".repeat(1 << 20);
+        let error = run(&formatter, &input).unwrap_err().to_string();
+        assert!(error.contains("bad input"), "{error}");
+        assert!(error.contains('3'), "{error}");
+    }
+}
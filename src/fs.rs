@@ -0,0 +1,240 @@
+//! Where buffers are read from and saved to.
+//!
+//! [`Disk`] is the plain filesystem. [`Compressed`] layers over another
+//! filesystem and transparently decompresses `.gz` and `.zst` files on read and
+//! recompresses them on write, so buffers only ever see text.
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub trait Filesystem: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    /// When `path` was last changed, to notice edits made elsewhere.
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// `path` made absolute, with `.`, `..` and symlinks resolved, so the same
+/// file always goes by the same name. A file that doesn't exist yet is
+/// resolved through its directory.
+pub fn normalize(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    match (dir.canonicalize(), path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+pub struct Disk;
+
+impl Filesystem for Disk {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    /// Writes in place rather than through a temporary file, so symlinks,
+    /// hard links, mode and owner all survive the save.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    pub fn detect(path: &Path) -> Option<Codec> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Codec::Gzip),
+            "zst" => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+
+    /// `path` without its compression extension, e.g. to detect the filetype
+    /// of what is inside.
+    pub fn strip(path: &Path) -> PathBuf {
+        match Codec::detect(path) {
+            Some(_) => path.with_extension(""),
+            None => path.to_path_buf(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        }
+    }
+
+    fn decode(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = vec![];
+        match self {
+            Codec::Gzip => {
+                GzDecoder::new(data).read_to_end(&mut out)?;
+            }
+            Codec::Zstd => out = zstd::decode_all(data)?,
+        }
+        Ok(out)
+    }
+
+    fn encode(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Codec::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Codec::Zstd => zstd::encode_all(data, 0),
+        }
+    }
+}
+
+pub struct Compressed<F>(pub F);
+
+impl<F: Filesystem> Filesystem for Compressed<F> {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let data = self.0.read(path)?;
+        match Codec::detect(path) {
+            Some(codec) => codec.decode(&data),
+            None => Ok(data),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        match Codec::detect(path) {
+            Some(codec) => self.0.write(path, &codec.encode(contents)?),
+            None => self.0.write(path, contents),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.0.modified(path)
+    }
+}
+
+/// Files kept in memory, for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct Memory(std::sync::Mutex<std::collections::HashMap<PathBuf, Vec<u8>>>);
+
+#[cfg(test)]
+impl Filesystem for Memory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn modified(&self, _path: &Path) -> io::Result<SystemTime> {
+        Ok(SystemTime::UNIX_EPOCH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "fn main() {\n    println!(\"héllo\");\n}\n";
+
+    #[test]
+    fn normalize_names_a_file_one_way() {
+        let dir = std::env::temp_dir().join(format!("sanguine-fs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let detour = dir.join(".").join("..").join(dir.file_name().unwrap());
+        assert_eq!(normalize(&detour.join("a.txt")), dir.join("a.txt"));
+        // Not created yet.
+        assert_eq!(normalize(&detour.join("b.txt")), dir.join("b.txt"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn normalize_makes_relative_paths_absolute() {
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(
+            normalize(Path::new("no-such-file.txt")),
+            cwd.join("no-such-file.txt")
+        );
+    }
+
+    #[test]
+    fn codecs_round_trip() {
+        for codec in [Codec::Gzip, Codec::Zstd] {
+            let encoded = codec.encode(TEXT.as_bytes()).unwrap();
+            assert_ne!(encoded, TEXT.as_bytes(), "{}", codec.name());
+            assert_eq!(codec.decode(&encoded).unwrap(), TEXT.as_bytes());
+        }
+    }
+
+    #[test]
+    fn detect_and_strip_the_extension() {
+        assert_eq!(Codec::detect(Path::new("a.rs.gz")), Some(Codec::Gzip));
+        assert_eq!(Codec::detect(Path::new("a.rs.zst")), Some(Codec::Zstd));
+        assert_eq!(Codec::detect(Path::new("a.rs")), None);
+        assert_eq!(Codec::strip(Path::new("a.rs.gz")), Path::new("a.rs"));
+        assert_eq!(Codec::strip(Path::new("a.rs")), Path::new("a.rs"));
+    }
+
+    #[test]
+    fn compressed_files_are_stored_compressed() {
+        let fs = Compressed(Memory::default());
+        let path = Path::new("notes.txt.gz");
+        fs.write(path, TEXT.as_bytes()).unwrap();
+        let stored = fs.0.read(path).unwrap();
+        assert_eq!(stored[..2], [0x1f, 0x8b]);
+        assert_eq!(fs.read_to_string(path).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn other_files_are_stored_as_they_are() {
+        let fs = Compressed(Memory::default());
+        let path = Path::new("notes.txt");
+        fs.write(path, TEXT.as_bytes()).unwrap();
+        assert_eq!(fs.0.read(path).unwrap(), TEXT.as_bytes());
+    }
+
+    #[test]
+    fn corrupt_data_is_an_error() {
+        let fs = Compressed(Memory::default());
+        let path = Path::new("notes.txt.zst");
+        fs.0.write(path, b"not zstd").unwrap();
+        assert!(fs.read(path).is_err());
+    }
+}
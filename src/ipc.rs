@@ -0,0 +1,182 @@
+//! `--reuse`: hand files to an already running instance over a unix socket.
+//!
+//! The first instance started with `--reuse` listens on the socket. Later ones
+//! connect, write one absolute path per line and exit.
+
+use std::{
+    fs::{DirBuilder, File},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+};
+
+use crate::{fs, runtime, Message};
+
+pub fn socket_path() -> io::Result<PathBuf> {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => Ok(PathBuf::from(dir).join("sanguine-demos.sock")),
+        None => Ok(private_dir()?.join("sanguine-demos.sock")),
+    }
+}
+
+/// A directory in the temp dir that only we can get into. The temp dir is
+/// shared between users, and any of them could bind a socket there first.
+fn private_dir() -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "sanguine-demos-{}",
+        std::env::var("USER").unwrap_or_default()
+    ));
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    let refuse = |why: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} {}", dir.display(), why),
+        ))
+    };
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() {
+        return refuse("is not a directory");
+    }
+    if meta.permissions().mode() & 0o077 != 0 {
+        return refuse("is open to other users");
+    }
+    // Whatever we create is owned by us, so it tells us who we are.
+    let probe = dir.join(".owner");
+    let ours = File::create(&probe)?.metadata()?.uid();
+    std::fs::remove_file(&probe).ok();
+    if meta.uid() != ours {
+        return refuse("belongs to another user");
+    }
+    Ok(dir)
+}
+
+/// Send `files` to a running instance. Returns false if there is none.
+pub fn send(files: &[PathBuf]) -> io::Result<bool> {
+    let mut stream = match UnixStream::connect(socket_path()?) {
+        Ok(stream) => stream,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(false)
+        }
+        Err(e) => return Err(e),
+    };
+    let cwd = std::env::current_dir()?;
+    for file in files {
+        writeln!(stream, "{}", cwd.join(file).display())?;
+    }
+    Ok(true)
+}
+
+/// Removes the socket when the listening instance exits.
+pub struct Listener {
+    path: PathBuf,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// The file a client asked for, named the way the editor names open files.
+/// Directories, relative paths, paths that don't end in a file name and files
+/// in directories that don't exist are turned away.
+fn target(line: &str) -> Option<PathBuf> {
+    let path = Path::new(line);
+    if !path.is_absolute() || path.file_name().is_none() {
+        return None;
+    }
+    let path = fs::normalize(path);
+    let dir = path.parent()?;
+    (dir.is_dir() && !path.is_dir()).then_some(path)
+}
+
+fn serve(stream: UnixStream) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match target(&line) {
+            Some(file) => runtime::post(Message::Open(file)),
+            None if line.is_empty() => {}
+            None => runtime::post(Message::Status(format!("--reuse: can't open {}", line))),
+        }
+    }
+}
+
+/// Start taking files from later `--reuse` launches.
+pub fn listen() -> io::Result<Listener> {
+    let path = socket_path()?;
+    // `send` found nobody listening, so any socket left is from a crash.
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    // Files can only be opened once the app takes messages. Until then
+    // clients wait in the listen backlog, with what they wrote buffered.
+    runtime::when_installed(move || {
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || serve(stream));
+            }
+        });
+    });
+    Ok(Listener { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test, with `file.txt` in it.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("sanguine-ipc-{}-{}", name, std::process::id()))
+            .join("sub");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("file.txt"), "").unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn line(path: &Path) -> String {
+        path.display().to_string()
+    }
+
+    #[test]
+    fn existing_files_are_resolved() {
+        let dir = scratch("existing");
+        let up = dir.join("..").join("sub").join(".").join("file.txt");
+        assert_eq!(target(&line(&up)), Some(dir.join("file.txt")));
+    }
+
+    #[test]
+    fn new_files_are_resolved_through_their_directory() {
+        let dir = scratch("new");
+        let new = dir.join("..").join("sub").join("new.txt");
+        assert_eq!(target(&line(&new)), Some(dir.join("new.txt")));
+    }
+
+    #[test]
+    fn directories_and_missing_directories_are_refused() {
+        let dir = scratch("refused");
+        assert_eq!(target(&line(&dir)), None);
+        assert_eq!(target(&line(&dir.join("missing").join("a.txt"))), None);
+        assert_eq!(target("/"), None);
+    }
+
+    #[test]
+    fn relative_paths_are_refused() {
+        assert_eq!(target("file.txt"), None);
+        assert_eq!(target(""), None);
+    }
+}
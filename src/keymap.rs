@@ -0,0 +1,577 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, KeyEvent, Modifiers, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, sync::Arc, sync::RwLock, time::Duration};
+
+use crate::{
+    error::{DemoError, DemoResult},
+    Message,
+};
+
+/// Something a key sequence can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Save,
+    Format,
+    Make,
+    OpenFile,
+    Diagnostics,
+    NextTab,
+    PreviousTab,
+    Search,
+    SearchNext,
+    SearchPrevious,
+    ClearSearch,
+    ReplaceInProject,
+    KeyInspector,
+    Buffers,
+    CloseOtherTabs,
+    CloseSavedTabs,
+    ReopenTab,
+    CopyLine,
+    CopyBuffer,
+    Snapshot,
+    SetLocal,
+    BufferInfo,
+}
+
+impl Action {
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Save => "save buffer",
+            Action::Format => "format buffer",
+            Action::Make => "run make command",
+            Action::OpenFile => "open file",
+            Action::Diagnostics => "list diagnostics",
+            Action::NextTab => "next tab",
+            Action::PreviousTab => "previous tab",
+            Action::Search => "search buffer",
+            Action::SearchNext => "next match",
+            Action::SearchPrevious => "previous match",
+            Action::ClearSearch => "clear search highlights",
+            Action::ReplaceInProject => "replace across the project",
+            Action::KeyInspector => "inspect key presses",
+            Action::Buffers => "list open buffers",
+            Action::CloseOtherTabs => "close other tabs",
+            Action::CloseSavedTabs => "close saved tabs",
+            Action::ReopenTab => "reopen closed tab",
+            Action::CopyLine => "copy line to clipboard",
+            Action::CopyBuffer => "copy buffer to clipboard",
+            Action::Snapshot => "save a snapshot of the screen",
+            Action::SetLocal => "set options for this buffer",
+            Action::BufferInfo => "show this buffer's options",
+        }
+    }
+}
+
+/// A key press as written in the keymap config, e.g. `ctrl+s` or `space`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl Key {
+    pub fn parse(s: &str) -> DemoResult<Key> {
+        let invalid = || DemoError::config(format!("invalid key `{}`", s));
+        // `+` and `ctrl++` bind the plus key itself, as do `plus` and `ctrl+plus`.
+        let (prefix, name) = match s.strip_suffix('+') {
+            Some("") => (None, "+"),
+            Some(rest) if rest.ends_with('+') => (rest.strip_suffix('+'), "+"),
+            _ => match s.rsplit_once('+') {
+                Some((prefix, name)) => (Some(prefix), name),
+                None => (None, s),
+            },
+        };
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        let mut modifiers = Modifiers::NONE;
+        for m in prefix.into_iter().flat_map(|p| p.split('+')) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" => Modifiers::CTRL,
+                "alt" | "meta" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "super" => Modifiers::SUPER,
+                _ => return Err(invalid()),
+            };
+        }
+        let code = match name.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "plus" => KeyCode::Char('+'),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Escape,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::UpArrow,
+            "down" => KeyCode::DownArrow,
+            "left" => KeyCode::LeftArrow,
+            "right" => KeyCode::RightArrow,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            n if n.len() > 1 && n.starts_with('f') => {
+                KeyCode::Function(n[1..].parse().map_err(|_| invalid())?)
+            }
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Ok(Key { code, modifiers })
+    }
+
+    /// The key as the keymap sees it.
+    ///
+    /// Only the four modifiers that can be written in the config count, and
+    /// shift is dropped from characters since it is already in their case.
+    pub fn from_event(event: &KeyEvent) -> Key {
+        let mut modifiers = event.modifiers
+            & (Modifiers::CTRL | Modifiers::ALT | Modifiers::SHIFT | Modifiers::SUPER);
+        if let KeyCode::Char(_) = event.key {
+            modifiers -= Modifiers::SHIFT;
+        }
+        Key {
+            code: event.key,
+            modifiers,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CTRL, "ctrl"),
+            (Modifiers::ALT, "alt"),
+            (Modifiers::SHIFT, "shift"),
+            (Modifiers::SUPER, "super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Escape => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::UpArrow => write!(f, "up"),
+            KeyCode::DownArrow => write!(f, "down"),
+            KeyCode::LeftArrow => write!(f, "left"),
+            KeyCode::RightArrow => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Function(n) => write!(f, "f{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// The `[keymap]` section of the config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeymapSettings {
+    /// The key `<leader>` stands for in bindings. Defaults to `ctrl+space`.
+    pub leader: Option<String>,
+    /// How long to wait for the next key of a sequence. Defaults to 1000.
+    pub timeout_ms: Option<u64>,
+    /// Key sequences, such as `"<leader> f"`, mapped to actions. These are
+    /// added to the default bindings, replacing any with the same sequence.
+    pub bindings: HashMap<String, Action>,
+}
+
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("ctrl+s", Action::Save),
+    ("alt+f", Action::Format),
+    ("alt+m", Action::Make),
+    ("ctrl+o", Action::OpenFile),
+    ("alt+d", Action::Diagnostics),
+    ("alt+b", Action::Buffers),
+    ("shift+right", Action::NextTab),
+    ("shift+left", Action::PreviousTab),
+    ("ctrl+f", Action::Search),
+    ("f3", Action::SearchNext),
+    ("shift+f3", Action::SearchPrevious),
+    ("esc", Action::ClearSearch),
+    ("<leader> w", Action::Save),
+    ("<leader> f", Action::Format),
+    ("<leader> m", Action::Make),
+    ("<leader> o", Action::OpenFile),
+    ("<leader> d", Action::Diagnostics),
+    ("<leader> n", Action::NextTab),
+    ("<leader> p", Action::PreviousTab),
+    ("<leader> /", Action::Search),
+    ("<leader> r", Action::ReplaceInProject),
+    ("<leader> k", Action::KeyInspector),
+    ("<leader> b", Action::Buffers),
+    ("<leader> t o", Action::CloseOtherTabs),
+    ("<leader> t s", Action::CloseSavedTabs),
+    ("<leader> t u", Action::ReopenTab),
+    ("<leader> y", Action::CopyLine),
+    ("<leader> Y", Action::CopyBuffer),
+    ("<leader> S", Action::Snapshot),
+    ("<leader> l", Action::SetLocal),
+    ("<leader> i", Action::BufferInfo),
+];
+
+pub enum Lookup {
+    Action(Action),
+    /// The keys so far start at least one longer binding.
+    Prefix,
+    None,
+}
+
+pub struct Keymap {
+    pub leader: Key,
+    pub timeout: Duration,
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+impl Keymap {
+    pub fn new(settings: &KeymapSettings) -> DemoResult<Keymap> {
+        let leader = Key::parse(settings.leader.as_deref().unwrap_or("ctrl+space"))?;
+        let mut keymap = Keymap {
+            leader,
+            timeout: Duration::from_millis(settings.timeout_ms.unwrap_or(1000)),
+            bindings: vec![],
+        };
+        for (sequence, action) in DEFAULT_BINDINGS {
+            keymap.bind(sequence, *action)?;
+        }
+        for (sequence, action) in &settings.bindings {
+            keymap.bind(sequence, *action)?;
+        }
+        Ok(keymap)
+    }
+
+    fn bind(&mut self, sequence: &str, action: Action) -> DemoResult<()> {
+        let keys = sequence
+            .split_whitespace()
+            .map(|key| match key {
+                "<leader>" => Ok(self.leader),
+                key => Key::parse(key),
+            })
+            .collect::<DemoResult<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Err(DemoError::config("empty key sequence"));
+        }
+        self.bindings.retain(|(k, _)| *k != keys);
+        self.bindings.push((keys, action));
+        Ok(())
+    }
+
+    pub fn lookup(&self, keys: &[Key]) -> Lookup {
+        let mut prefix = false;
+        for (sequence, action) in &self.bindings {
+            if sequence.as_slice() == keys {
+                return Lookup::Action(*action);
+            }
+            prefix |= sequence.starts_with(keys);
+        }
+        if prefix {
+            Lookup::Prefix
+        } else {
+            Lookup::None
+        }
+    }
+
+    /// The keys that can follow `keys`, with what each leads to.
+    pub fn continuations(&self, keys: &[Key]) -> Vec<(Key, String)> {
+        let mut next: Vec<(Key, String)> = vec![];
+        for (sequence, action) in &self.bindings {
+            if sequence.len() <= keys.len() || !sequence.starts_with(keys) {
+                continue;
+            }
+            let key = sequence[keys.len()];
+            if next.iter().any(|(k, _)| *k == key) {
+                continue;
+            }
+            let label = if sequence.len() == keys.len() + 1 {
+                action.description().to_owned()
+            } else {
+                "+prefix".to_owned()
+            };
+            next.push((key, label));
+        }
+        next
+    }
+
+    /// Every binding in effect, as a `[keymap]` config section.
+    pub fn dump(&self) -> String {
+        let mut bindings = toml::Table::new();
+        for (keys, action) in &self.bindings {
+            if let Ok(action) = toml::Value::try_from(action) {
+                bindings.insert(self.describe(keys), action);
+            }
+        }
+        let mut keymap = toml::Table::new();
+        keymap.insert("leader".into(), self.leader.to_string().into());
+        keymap.insert(
+            "timeout_ms".into(),
+            (self.timeout.as_millis() as i64).into(),
+        );
+        keymap.insert("bindings".into(), bindings.into());
+        let mut config = toml::Table::new();
+        config.insert("keymap".into(), keymap.into());
+        toml::to_string(&config).unwrap_or_default()
+    }
+
+    /// Every sequence bound to `action`, for telling the user what to press.
+    pub fn keys_for(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(keys, _)| self.describe(keys))
+            .collect();
+        if keys.is_empty() {
+            format!("(unbound: {})", action.description())
+        } else {
+            keys.join(" or ")
+        }
+    }
+
+    /// Spell out a sequence the way it is written in the config.
+    pub fn describe(&self, keys: &[Key]) -> String {
+        keys.iter()
+            .map(|k| {
+                if *k == self.leader {
+                    "<leader>".to_owned()
+                } else {
+                    k.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A popup listing what the keys of a pending sequence can be followed by.
+/// It never takes focus; the editor removes it when the sequence ends.
+pub struct WhichKey {
+    title: String,
+    entries: Vec<(Key, String)>,
+}
+
+impl WhichKey {
+    pub fn new(keymap: &Keymap, keys: &[Key]) -> WhichKey {
+        WhichKey {
+            title: keymap.describe(keys),
+            entries: keymap.continuations(keys),
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        self.entries.len() as f32 + 2.
+    }
+}
+
+impl Widget<Message, ()> for WhichKey {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let lines: Vec<Spans> = self
+            .entries
+            .iter()
+            .map(|(key, label)| {
+                Spans::from(vec![
+                    Span::styled(format!("{:>8} ", key), Style::default().fg(Color::Yellow)),
+                    Span::raw(label.as_str()),
+                ])
+            })
+            .collect();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str());
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        _cx: &mut UpdateCtx<'u, Message, ()>,
+        _event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        Ok(())
+    }
+}
+
+/// How many key presses the inspector keeps on screen.
+const INSPECTOR_HISTORY: usize = 12;
+
+/// Shows exactly what the terminal reports for each key press, how to write it
+/// in the keymap config, and what it is bound to. Esc twice in a row closes it,
+/// so a single Escape can still be inspected.
+pub struct KeyInspector {
+    keymap: Arc<Keymap>,
+    events: Vec<KeyEvent>,
+}
+
+impl KeyInspector {
+    pub fn new(keymap: Arc<Keymap>) -> KeyInspector {
+        KeyInspector {
+            keymap,
+            events: vec![],
+        }
+    }
+}
+
+impl Widget<Message, ()> for KeyInspector {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut lines = vec![Spans::from(Span::styled(
+            "press keys to inspect them, esc esc to close",
+            dim,
+        ))];
+        for event in self.events.iter().rev() {
+            let key = Key::from_event(event);
+            let bound = match self.keymap.lookup(&[key]) {
+                Lookup::Action(action) => action.description(),
+                Lookup::Prefix => "+prefix",
+                Lookup::None => "",
+            };
+            lines.push(Spans::from(vec![
+                Span::styled(format!("{:<14} ", key), Style::default().fg(Color::Yellow)),
+                Span::raw(format!("{:?} {:?} ", event.key, event.modifiers)),
+                Span::styled(bound, Style::default().fg(Color::Green)),
+            ]));
+        }
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default().borders(Borders::ALL).title("Keys");
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let Event::Key(key) = event else {
+            return Ok(());
+        };
+        let escape = |k: &KeyEvent| k.key == KeyCode::Escape && k.modifiers == Modifiers::NONE;
+        if escape(&key) && self.events.last().map_or(false, escape) {
+            cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            return Ok(());
+        }
+        self.events.push(key);
+        if self.events.len() > INSPECTOR_HISTORY {
+            self.events.remove(0);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: Modifiers) -> Key {
+        Key { code, modifiers }
+    }
+
+    #[test]
+    fn parse_modifiers_and_names() {
+        assert_eq!(
+            Key::parse("ctrl+s").unwrap(),
+            key(KeyCode::Char('s'), Modifiers::CTRL)
+        );
+        assert_eq!(
+            Key::parse("Ctrl+Shift+F5").unwrap(),
+            key(KeyCode::Function(5), Modifiers::CTRL | Modifiers::SHIFT)
+        );
+        assert_eq!(
+            Key::parse("meta+space").unwrap(),
+            key(KeyCode::Char(' '), Modifiers::ALT)
+        );
+        assert_eq!(
+            Key::parse("esc").unwrap(),
+            key(KeyCode::Escape, Modifiers::NONE)
+        );
+    }
+
+    #[test]
+    fn parse_keeps_the_case_of_characters() {
+        assert_eq!(
+            Key::parse("ctrl+S").unwrap(),
+            key(KeyCode::Char('S'), Modifiers::CTRL)
+        );
+    }
+
+    #[test]
+    fn parse_plus_key() {
+        for s in ["ctrl++", "ctrl+plus", "Ctrl+Plus"] {
+            assert_eq!(
+                Key::parse(s).unwrap(),
+                key(KeyCode::Char('+'), Modifiers::CTRL),
+                "{:?}",
+                s
+            );
+        }
+        for s in ["+", "plus"] {
+            assert_eq!(
+                Key::parse(s).unwrap(),
+                key(KeyCode::Char('+'), Modifiers::NONE),
+                "{:?}",
+                s
+            );
+        }
+        assert_eq!(
+            Key::parse("ctrl+alt++").unwrap(),
+            key(KeyCode::Char('+'), Modifiers::CTRL | Modifiers::ALT)
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_keys() {
+        for s in ["", "ctrl+", "hyper+a", "ab", "fx", "++", "ctrl+++", "+a"] {
+            assert!(Key::parse(s).is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for s in ["ctrl+s", "alt+shift+enter", "space", "f12", "ctrl++", "+"] {
+            let key = Key::parse(s).unwrap();
+            assert_eq!(Key::parse(&key.to_string()).unwrap(), key);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Options set on one buffer at runtime, like Vim's `:setlocal`.
+//!
+//! The setlocal prompt takes options the way Vim writes them: `wrap` and
+//! `nowrap` for switches, `tabwidth=2` for values, several separated by
+//! spaces. They last until the buffer is closed and never touch the config
+//! file, whose `[buffer]` section only gives the defaults. [`BufferInfo`] shows
+//! where a buffer's options stand.
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    error::{DemoError, DemoResult},
+    Message,
+};
+
+/// Widest tab stop setlocal accepts.
+const MAX_TAB_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Wrap(bool),
+    ExpandTab(bool),
+    TabWidth(usize),
+    Readonly(bool),
+    /// `None` turns off whatever the filetype was detected as.
+    Filetype(Option<String>),
+}
+
+/// Parse what was typed at the setlocal prompt.
+pub fn parse(input: &str) -> DemoResult<Vec<Setting>> {
+    input.split_whitespace().map(setting).collect()
+}
+
+fn setting(word: &str) -> DemoResult<Setting> {
+    let (name, value) = match word.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (word, None),
+    };
+    let (name, on) = match name.strip_prefix("no") {
+        Some(rest)
+            if value.is_none()
+                && matches!(rest, "wrap" | "expandtab" | "et" | "readonly" | "ro") =>
+        {
+            (rest, false)
+        }
+        _ => (name, true),
+    };
+    match (name, value) {
+        ("wrap", None) => Ok(Setting::Wrap(on)),
+        ("expandtab" | "et", None) => Ok(Setting::ExpandTab(on)),
+        ("readonly" | "ro", None) => Ok(Setting::Readonly(on)),
+        ("tabwidth" | "ts", Some(value)) => match value.parse() {
+            Ok(width @ 1..=MAX_TAB_WIDTH) => Ok(Setting::TabWidth(width)),
+            _ => Err(DemoError::config(format!(
+                "tabwidth must be between 1 and {}, not {}",
+                MAX_TAB_WIDTH, value
+            ))),
+        },
+        ("filetype" | "ft", Some("")) => Ok(Setting::Filetype(None)),
+        ("filetype" | "ft", Some(value)) => Ok(Setting::Filetype(Some(value.to_owned()))),
+        ("wrap" | "expandtab" | "et" | "readonly" | "ro", Some(_)) => Err(DemoError::config(
+            format!("{} is a switch; use {0} or no{0}", name),
+        )),
+        ("tabwidth" | "ts" | "filetype" | "ft", None) => Err(DemoError::config(format!(
+            "{} needs a value, like {0}=...",
+            name
+        ))),
+        _ => Err(DemoError::config(format!("unknown option {}", name))),
+    }
+}
+
+/// A floating list of the current buffer's options, as of when it opened.
+/// Esc, q or Enter closes it.
+pub struct BufferInfo {
+    title: String,
+    rows: Vec<(&'static str, String)>,
+}
+
+impl BufferInfo {
+    pub fn new(title: String, rows: Vec<(&'static str, String)>) -> BufferInfo {
+        BufferInfo { title, rows }
+    }
+
+    /// How tall the popup needs to be, borders and hint included.
+    pub fn height(&self) -> f32 {
+        self.rows.len() as f32 + 4.
+    }
+}
+
+impl Widget<Message, ()> for BufferInfo {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let mut lines: Vec<Spans> = self
+            .rows
+            .iter()
+            .map(|(name, value)| {
+                Spans::from(vec![
+                    Span::styled(format!("{:<12} ", name), Style::default().fg(Color::Yellow)),
+                    Span::raw(value.as_str()),
+                ])
+            })
+            .collect();
+        lines.push(Spans::default());
+        lines.push(Spans::from(Span::styled(
+            "change with setlocal, e.g. nowrap tabwidth=2",
+            Style::default().fg(Color::DarkGray),
+        )));
+        let title = self.title.as_str();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default().borders(Borders::ALL).title(title);
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        match event {
+            Event::Key(k)
+                if matches!(k.key, KeyCode::Escape | KeyCode::Char('q') | KeyCode::Enter) =>
+            {
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> String {
+        parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn switches_and_their_negations() {
+        assert_eq!(
+            parse("wrap noet ro noreadonly").unwrap(),
+            vec![
+                Setting::Wrap(true),
+                Setting::ExpandTab(false),
+                Setting::Readonly(true),
+                Setting::Readonly(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_and_short_names() {
+        assert_eq!(
+            parse("  ts=4   filetype=rust ft=  ").unwrap(),
+            vec![
+                Setting::TabWidth(4),
+                Setting::Filetype(Some("rust".to_owned())),
+                Setting::Filetype(None),
+            ]
+        );
+        assert_eq!(parse("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn tab_width_must_be_in_range() {
+        assert_eq!(parse("tabwidth=16").unwrap(), vec![Setting::TabWidth(16)]);
+        for width in ["0", "17", "two"] {
+            assert_eq!(
+                error(&format!("ts={}", width)),
+                format!("tabwidth must be between 1 and 16, not {}", width)
+            );
+        }
+    }
+
+    #[test]
+    fn switches_take_no_value_and_values_need_one() {
+        assert_eq!(error("wrap=yes"), "wrap is a switch; use wrap or nowrap");
+        assert_eq!(error("ts"), "ts needs a value, like ts=...");
+        assert_eq!(error("nowrap=1"), "unknown option nowrap");
+    }
+
+    #[test]
+    fn only_switches_can_be_negated() {
+        assert_eq!(error("notabwidth"), "unknown option notabwidth");
+        assert_eq!(error("wrap bogus"), "unknown option bogus");
+    }
+}
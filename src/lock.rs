@@ -0,0 +1,156 @@
+//! Lock files that stop two instances from editing the same file unawares.
+//!
+//! Opening a file drops `.<name>.sanguine-lock` next to it holding our pid.
+//! Locks are advisory: they only warn other instances, nothing stops a write.
+//! A lock whose process is gone is stale and gets taken over, and "edit
+//! anyway" takes over a live one.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Held for as long as a buffer has its file open for editing.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another live instance, with this pid, has the file open.
+    Held(u32),
+    Io(io::Error),
+}
+
+fn lock_path(file: &Path) -> PathBuf {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    file.with_file_name(format!(".{}.sanguine-lock", name))
+}
+
+/// The pid written in the lock file at `path`.
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The pid of another live instance that has `file` locked.
+pub fn holder(file: &Path) -> Option<u32> {
+    read_pid(&lock_path(file)).filter(|&pid| pid != std::process::id() && alive(pid))
+}
+
+#[cfg(unix)]
+fn alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists. EPERM means it does, but
+    // belongs to someone else.
+    unsafe { libc::kill(pid, 0) == 0 }
+    || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn alive(_pid: u32) -> bool {
+    // Without a cheap way to check, assume the holder is still running.
+    true
+}
+
+impl FileLock {
+    pub fn acquire(file: &Path) -> Result<FileLock, LockError> {
+        let path = lock_path(file);
+        // Once for a fresh lock, once more after clearing a stale one.
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut f) => {
+                    writeln!(f, "{}", std::process::id()).map_err(LockError::Io)?;
+                    return Ok(FileLock { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match holder(file) {
+                    Some(pid) => return Err(LockError::Held(pid)),
+                    None => std::fs::remove_file(&path).map_err(LockError::Io)?,
+                },
+                Err(e) => return Err(LockError::Io(e)),
+            }
+        }
+        Err(LockError::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "lock file keeps reappearing",
+        )))
+    }
+
+    /// Take the lock even though another instance holds it. That instance
+    /// isn't told, but any started after this are warned about us.
+    pub fn take(file: &Path) -> io::Result<FileLock> {
+        let path = lock_path(file);
+        std::fs::write(&path, format!("{}\n", std::process::id()))?;
+        Ok(FileLock { path })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Taken over since; it is the new holder's to remove.
+        if read_pid(&self.path) == Some(std::process::id()) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sanguine-lock-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("file.txt")
+    }
+
+    /// A pid no process has: above the largest pid_max allows.
+    const GONE: u32 = 1 << 23;
+
+    #[test]
+    fn dropping_the_lock_removes_it() {
+        let file = scratch("drop");
+        let lock = FileLock::acquire(&file).unwrap();
+        assert_eq!(read_pid(&lock_path(&file)), Some(std::process::id()));
+        // Our own lock doesn't count as another instance's.
+        assert_eq!(holder(&file), None);
+        drop(lock);
+        assert!(!lock_path(&file).exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn live_locks_are_held() {
+        let file = scratch("held");
+        // Our parent is alive, and isn't us.
+        let parent = std::os::unix::process::parent_id();
+        std::fs::write(lock_path(&file), format!("{}\n", parent)).unwrap();
+        assert_eq!(holder(&file), Some(parent));
+        assert!(matches!(FileLock::acquire(&file), Err(LockError::Held(pid)) if pid == parent));
+        std::fs::remove_file(lock_path(&file)).unwrap();
+    }
+
+    #[test]
+    fn stale_locks_are_taken_over() {
+        let file = scratch("stale");
+        std::fs::write(lock_path(&file), format!("{}\n", GONE)).unwrap();
+        assert_eq!(holder(&file), None);
+        let lock = FileLock::acquire(&file).unwrap();
+        assert_eq!(read_pid(&lock.path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn taken_over_locks_are_left_to_their_holder() {
+        let file = scratch("take");
+        let lock = FileLock::take(&file).unwrap();
+        // Another instance took it from us in turn.
+        std::fs::write(lock_path(&file), format!("{}\n", GONE)).unwrap();
+        drop(lock);
+        assert_eq!(read_pid(&lock_path(&file)), Some(GONE));
+        std::fs::remove_file(lock_path(&file)).unwrap();
+    }
+}
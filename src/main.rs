@@ -1,54 +1,234 @@
 use ratatui::{
     self as tui,
     backend::Backend,
+    layout::Alignment,
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Tabs},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs},
     Frame,
 };
 use sanguine::{
     bridge::{Bridge, BridgeInner},
     error::*,
-    event::{Event, KeyCode, Modifiers, UserEvent},
+    event::{Event, KeyCode, KeyEvent, Modifiers, UserEvent},
     layout::{Constraint, NodeId, Rect},
     surface::Surface,
-    widgets::{Border, Menu, TextBox},
+    widgets::{Border, TextBox},
     App, Config, RenderCtx, UpdateCtx, Widget,
 };
 
 use std::{
-    path::PathBuf,
-    sync::{atomic::AtomicBool, Arc, RwLock},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
 };
+use termwiz::cell::unicode_column_width;
 
-pub struct FileDialog<U> {
-    pwd: Arc<RwLock<PathBuf>>,
-    dirty: Arc<AtomicBool>,
-    menu: Arc<RwLock<Menu<U>>>,
+mod bidi;
+mod cli;
+mod compose;
+mod conflict;
+mod diagnostics;
+mod diff;
+mod error;
+mod filetype;
+mod focus;
+mod format;
+mod fs;
+#[cfg(unix)]
+mod ipc;
+mod keymap;
+mod local;
+mod lock;
+mod notify;
+mod osc;
+mod pacing;
+mod picker;
+mod preview;
+mod project;
+mod prompt;
+mod quickfix;
+mod runtime;
+mod search;
+mod selection;
+mod session;
+mod settings;
+mod snapshot;
+mod supervise;
+mod theme;
+mod tutor;
+mod view;
+
+use bidi::CursorMovement;
+use clap::{CommandFactory, Parser};
+use cli::{Cli, Command, ConfigCommand, KeymapCommand};
+use compose::Composer;
+use conflict::{ConflictPanel, Resolution};
+use diagnostics::{Diagnostics, DiagnosticsPanel, Severity};
+use diff::DiffView;
+use error::{Context, DemoError, DemoResult, Poisoned};
+use focus::{Focus, FocusReports};
+use fs::{Codec, Compressed, Disk, Filesystem};
+use keymap::{Action, Key, KeyInspector, Keymap, Lookup, WhichKey};
+use local::{BufferInfo, Setting};
+use lock::{FileLock, LockError};
+use pacing::FramePacer;
+use picker::{BufferEntry, BufferPicker};
+use preview::{Preview, PreviewPane};
+use project::ReplacePanel;
+use prompt::{Prompt, PromptEvent};
+use search::Search;
+use selection::Selection;
+use session::Session;
+use settings::{AutosaveSettings, BufferSettings, Settings};
+use supervise::Supervised;
+use theme::Theme;
+use tutor::{Exercise, Tutor};
+use view::EditorView;
+
+/// One row of the [`FileDialog`].
+struct FileEntry {
+    name: String,
+    path: PathBuf,
+    dir: bool,
+}
+
+/// Lists a directory, starting from the working directory. Enter opens the
+/// file under the cursor or goes into the directory, and the preview pane
+/// follows the selection.
+pub struct FileDialog {
+    pwd: PathBuf,
+    /// `..` first, then the files and directories in `pwd`.
+    entries: Vec<FileEntry>,
+    selection: Selection<ListState>,
 }
 
-impl FileDialog<Message> {
-    pub fn new() -> FileDialog<Message> {
-        FileDialog {
-            pwd: Arc::new(RwLock::new(std::env::current_dir().unwrap())),
-            dirty: Arc::new(AtomicBool::new(true)),
-            menu: Arc::new(RwLock::new(Menu::new("Files"))),
+impl FileDialog {
+    pub fn new() -> DemoResult<FileDialog> {
+        let mut dialog = FileDialog {
+            pwd: PathBuf::new(),
+            entries: vec![],
+            selection: Selection::default(),
+        };
+        dialog.cd(std::env::current_dir().context("finding the working directory")?)?;
+        Ok(dialog)
+    }
+
+    fn cd(&mut self, dir: PathBuf) -> DemoResult<()> {
+        let mut entries = vec![FileEntry {
+            name: "..".to_owned(),
+            path: dir
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from("/")),
+            dir: true,
+        }];
+        for entry in std::fs::read_dir(&dir).context("listing files")? {
+            let entry = entry.context("listing files")?;
+            let path = entry.path();
+            if path.is_file() || path.is_dir() {
+                entries.push(FileEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    dir: path.is_dir(),
+                    path,
+                });
+            }
         }
+        self.pwd = dir;
+        self.entries = entries;
+        self.selection.select(0);
+        Ok(())
+    }
+
+    /// Show the selected file in the preview pane, or close it for a directory.
+    fn preview(&self, cx: &mut UpdateCtx<'_, Message, ()>) {
+        let target = self
+            .entries
+            .get(self.selection.index())
+            .filter(|e| !e.dir)
+            .map(|e| (e.path.clone(), 0));
+        cx.tx.send(UserEvent::User(Message::Preview(target))).ok();
     }
 }
 
 pub enum Message {
+    /// A key binding that needs the app, such as one that opens a floating tool.
+    Action(Action),
     Open(PathBuf),
+    /// Open a file and move the cursor to `(line, column)`.
+    Goto(PathBuf, usize, usize),
     Close(NodeId),
+    /// Close the tab showing this file, unless it has unsaved changes.
+    CloseBuffer(PathBuf),
+    /// A background formatter finished running over `file`.
+    Formatted {
+        file: PathBuf,
+        /// The text the formatter was given.
+        input: String,
+        result: DemoResult<String>,
+        save: bool,
+    },
+    /// Show a message in the status bar.
+    Status(String),
+    /// A file with unsaved edits changed on disk.
+    DiskChanged(PathBuf),
+    /// What to do about a [`Message::DiskChanged`] file, which now holds `theirs`.
+    Resolve {
+        file: PathBuf,
+        theirs: String,
+        resolution: Resolution,
+    },
+    /// Point the preview pane at a line of a file, opening it without moving
+    /// focus, or close it with `None`.
+    Preview(Option<(PathBuf, usize)>),
+    /// Show a notification raised by [`notify::notify`].
+    Notify {
+        summary: String,
+        body: String,
+    },
+    /// A project-wide replace was scanned and is ready for review.
+    ReplacePreview(Vec<project::FileChanges>),
+    /// The reviewed replacements are to be written.
+    ApplyReplace(Vec<project::FileChanges>),
+    /// The reviewed replacements were written.
+    Replaced(project::ReplaceSummary),
+    /// Replace everything reported by `source` with a fresh set of diagnostics.
+    Diagnostics {
+        source: String,
+        diagnostics: Vec<(PathBuf, diagnostics::Diagnostic)>,
+    },
 }
 
-impl Widget<Message, ()> for FileDialog<Message> {
+impl Widget<Message, ()> for FileDialog {
     fn render<'r>(
         &self,
-        cx: &RenderCtx<'r, Message, ()>,
+        _cx: &RenderCtx<'r, Message, ()>,
         surface: &mut Surface,
     ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
-        Border::from_inner("Files", self.menu.clone()).render(cx, surface)
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|e| match e.dir {
+                true => ListItem::new(format!("{}/", e.name)),
+                false => ListItem::new(e.name.as_str()),
+            })
+            .collect();
+        let title = self.pwd.display().to_string();
+        let len = items.len();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let list = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                let area = f.size();
+                self.selection.render(f, list, area, len);
+            })
+            .unwrap();
+        None
     }
 
     fn update<'u>(
@@ -56,92 +236,435 @@ impl Widget<Message, ()> for FileDialog<Message> {
         cx: &mut UpdateCtx<'u, Message, ()>,
         event: Event<Message>,
     ) -> sanguine::error::Result<()> {
-        if self.dirty.swap(false, std::sync::atomic::Ordering::SeqCst) == true {
-            let mut menu = self.menu.write().unwrap();
-            menu.clear();
-            let pwd = self.pwd.clone();
-            let dirty = self.dirty.clone();
-            menu.add_item("..", "", move |_, _, _| {
-                let mut pwd = pwd.write().unwrap();
-                *pwd = pwd
-                    .parent()
-                    .map(|p| p.to_path_buf())
-                    .unwrap_or_else(|| PathBuf::from("/"));
-                dirty.store(true, std::sync::atomic::Ordering::SeqCst);
-            });
-            for entry in
-                std::fs::read_dir(self.pwd.read().unwrap().as_path()).map_err(Error::external)?
-            {
-                let entry = entry.map_err(Error::external)?;
-                let path = entry.path();
-                let buf = path.to_path_buf();
-                let name = entry.file_name();
-                let pwd = self.pwd.clone();
-                let dirty = self.dirty.clone();
-                if path.is_file() {
-                    let owner = cx.owner;
-                    menu.add_item(name.to_string_lossy(), "", move |_, _, tx| {
-                        tx.send(UserEvent::User(Message::Open(buf.clone()))).ok();
-                        tx.send(UserEvent::User(Message::Close(owner))).ok();
-                    });
-                } else if path.is_dir() {
-                    menu.add_item(name.to_string_lossy(), "", move |_, _, tx| {
-                        let mut pwd = pwd.write().unwrap();
-                        *pwd = buf.clone();
-                        tx.send(UserEvent::Tick).ok();
-                        dirty.store(true, std::sync::atomic::Ordering::SeqCst);
-                    });
-                }
-            }
-        }
-        match &event {
+        match event {
             Event::Key(k) if k.key == KeyCode::Escape || k.key == KeyCode::Char('q') => {
+                cx.tx.send(UserEvent::User(Message::Preview(None))).ok();
                 cx.layout.remove_node(cx.owner);
-                return Ok(());
+            }
+            Event::Key(k) if k.key == KeyCode::Enter => {
+                match self.entries.get(self.selection.index()) {
+                    Some(entry) if entry.dir => {
+                        let dir = entry.path.clone();
+                        self.cd(dir)?;
+                        self.preview(cx);
+                    }
+                    Some(entry) => {
+                        cx.tx
+                            .send(UserEvent::User(Message::Open(entry.path.clone())))
+                            .ok();
+                        cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+                    }
+                    None => {}
+                }
+            }
+            Event::Key(k) => {
+                if self.selection.handle(k.key, self.entries.len()) {
+                    self.preview(cx);
+                }
             }
             _ => {}
         }
-        self.menu.write().unwrap().update(cx, event)?;
         Ok(())
     }
 }
 
 pub struct Buffer {
     file: PathBuf,
+    filetype: Option<String>,
     editor: Arc<RwLock<TextBox>>,
+    settings: Arc<Settings>,
+    /// Options set with setlocal, starting from the `[buffer]` settings.
+    wrap: bool,
+    expand_tab: bool,
+    tab_width: usize,
+    /// Columns of text the view last had room for, which wrapped lines fold at.
+    text_width: AtomicUsize,
+    diagnostics: Arc<RwLock<Diagnostics>>,
+    search: Arc<RwLock<Search>>,
+    theme: Arc<Theme>,
+    /// The text before and after the last whole-buffer replacement, so that
+    /// it can be undone as a single edit.
+    replaced: Option<(String, String)>,
+    /// A jump requested from outside an update, applied on the next event,
+    /// which [`Buffer::goto_later`] makes sure comes soon.
+    pending_goto: Option<(usize, usize)>,
+    /// The text as last loaded or saved.
+    saved: String,
+    /// Our claim on the file, while no other instance has it.
+    lock: Option<FileLock>,
+    /// Refuses edits and saves, e.g. while another instance edits the file.
+    readonly: bool,
+    composer: Composer,
+    fs: Arc<dyn Filesystem>,
+    /// How the file is compressed on disk, if it is.
+    codec: Option<Codec>,
+    /// When the file was last changed on disk, as of the last load, save or
+    /// look at it.
+    mtime: Option<SystemTime>,
+    /// A change on disk is waiting to be settled with [`Buffer::resolve`].
+    conflict: bool,
 }
 
 impl Buffer {
-    pub fn new(file: PathBuf) -> Result<Buffer> {
+    pub fn new(
+        file: PathBuf,
+        settings: Arc<Settings>,
+        diagnostics: Arc<RwLock<Diagnostics>>,
+        search: Arc<RwLock<Search>>,
+        theme: Arc<Theme>,
+        fs: Arc<dyn Filesystem>,
+    ) -> DemoResult<Buffer> {
         let text = if !file.exists() {
             String::new()
         } else {
-            std::fs::read_to_string(&file).map_err(Error::external)?
+            fs.read_to_string(&file)
+                .context(format!("opening {}", file.display()))?
         };
-        Ok(Buffer {
+        let mut buffer = Buffer {
+            filetype: filetype::detect(&Codec::strip(&file)).map(str::to_owned),
+            codec: Codec::detect(&file),
             file,
+            fs,
             editor: Arc::new(RwLock::new(TextBox::from_str(text))),
-        })
+            wrap: settings.buffer.wrap,
+            expand_tab: settings.buffer.expand_tab,
+            tab_width: settings.tab_width(),
+            text_width: AtomicUsize::new(0),
+            settings,
+            diagnostics,
+            search,
+            theme,
+            replaced: None,
+            pending_goto: None,
+            saved: String::new(),
+            lock: None,
+            readonly: false,
+            composer: Composer::default(),
+            mtime: None,
+            conflict: false,
+        };
+        buffer.saved = buffer.text()?;
+        buffer.mtime = buffer.disk_mtime();
+        Ok(buffer)
     }
 
-    pub fn load(&mut self) -> Result<()> {
-        let text = std::fs::read_to_string(&self.file).map_err(Error::external)?;
+    pub fn load(&mut self) -> DemoResult<()> {
+        let text = self
+            .fs
+            .read_to_string(&self.file)
+            .context(format!("reloading {}", self.file.display()))?;
         self.editor = Arc::new(RwLock::new(TextBox::from_str(text)));
+        self.saved = self.text()?;
+        self.mtime = self.disk_mtime();
+        Ok(())
+    }
+
+    pub fn save(&mut self) -> DemoResult<()> {
+        if self.readonly {
+            return Err(DemoError::refused("buffer is read-only"));
+        }
+        let text = self.text()?;
+        self.fs
+            .write(&self.file, text.as_bytes())
+            .context(format!("saving {}", self.file.display()))?;
+        self.saved = text;
+        self.mtime = self.disk_mtime();
+        Ok(())
+    }
+
+    /// When the file was last changed on disk, if it exists.
+    fn disk_mtime(&self) -> Option<SystemTime> {
+        self.fs.modified(&self.file).ok()
+    }
+
+    /// Whether the file changed on disk since it was last looked at. Each
+    /// change is only reported once.
+    fn changed_on_disk(&mut self) -> bool {
+        let now = self.disk_mtime();
+        if now.is_none() || now == self.mtime {
+            return false;
+        }
+        self.mtime = now;
+        true
+    }
+
+    /// Whether saving now would overwrite a change on disk we haven't seen or
+    /// haven't settled yet.
+    fn behind_disk(&self) -> bool {
+        self.conflict
+            || self
+                .disk_mtime()
+                .map_or(false, |now| Some(now) != self.mtime)
+    }
+
+    /// Settle a conflict with the file on disk, which now holds `theirs`.
+    fn resolve(&mut self, resolution: &Resolution, theirs: &str) -> DemoResult<()> {
+        match resolution {
+            Resolution::Keep => {}
+            Resolution::Take => self.replace_text(theirs.to_owned())?,
+            Resolution::Merge(text) => self.replace_text(text.clone())?,
+        }
+        self.conflict = false;
+        // Later conflicts are against what is on disk now.
+        self.saved = TextBox::from_str(theirs.to_owned())
+            .buffer()
+            .read()
+            .or_poisoned("buffer text")?
+            .join("\n");
+        Ok(())
+    }
+
+    /// Take the lock on the file. If another instance holds it, the buffer
+    /// turns read-only.
+    fn lock(&mut self) -> Result<(), LockError> {
+        match FileLock::acquire(&self.file) {
+            Ok(lock) => {
+                self.lock = Some(lock);
+                Ok(())
+            }
+            Err(e) => {
+                if let LockError::Held(_) = e {
+                    self.readonly = true;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Take the lock from the instance holding it, to edit the file anyway.
+    fn take_lock(&mut self) -> DemoResult<()> {
+        let lock =
+            FileLock::take(&self.file).context(format!("locking {}", self.file.display()))?;
+        self.lock = Some(lock);
+        self.readonly = false;
+        Ok(())
+    }
+
+    fn unlock(&mut self) {
+        self.lock = None;
+    }
+
+    pub fn modified(&self) -> bool {
+        self.text().map_or(false, |text| text != self.saved)
+    }
+
+    pub fn text(&self) -> DemoResult<String> {
+        Ok(self
+            .editor
+            .read()
+            .or_poisoned("editor")?
+            .buffer()
+            .read()
+            .or_poisoned("buffer text")?
+            .join("\n"))
+    }
+
+    /// Replace the whole buffer, keeping the cursor where it was.
+    ///
+    /// The replacement is recorded so that a single undo restores the old text.
+    pub fn replace_text(&mut self, text: String) -> DemoResult<()> {
+        if self.readonly {
+            return Err(DemoError::refused("buffer is read-only"));
+        }
+        let before = self.text()?;
+        if before == text {
+            return Ok(());
+        }
+        self.set_text(&text)?;
+        self.replaced = Some((before, text));
+        Ok(())
+    }
+
+    /// Swap in a fresh TextBox. The cursor is put back on the next update.
+    fn set_text(&mut self, text: &str) -> DemoResult<()> {
+        let position = self.pending_goto.unwrap_or_else(|| self.position());
+        *self.editor.write().or_poisoned("editor")? = TextBox::from_str(text.to_owned());
+        self.goto_later(position.0, position.1);
         Ok(())
     }
 
-    pub fn save(&self) -> Result<()> {
-        std::fs::write(
-            &self.file,
+    /// Move the cursor from outside an update. The TextBox can only move
+    /// inside one, so this asks for a tick to do it in.
+    fn goto_later(&mut self, x: usize, y: usize) {
+        self.pending_goto = Some((x, y));
+        runtime::tick();
+    }
+
+    /// The cursor as a `(column, line)` position in the text.
+    fn position(&self) -> (usize, usize) {
+        self.editor
+            .read()
+            .ok()
+            .and_then(|editor| <TextBox as Widget<Message, ()>>::cursor(&editor))
+            .map(|(_, x, y)| (x, y))
+            .unwrap_or_default()
+    }
+
+    fn line(&self, y: usize) -> Option<String> {
+        let editor = self.editor.read().ok()?;
+        let lines = editor.buffer().read().ok()?;
+        lines.get(y).cloned()
+    }
+
+    /// Move the cursor to column `x` of line `y`, clamped to the buffer.
+    ///
+    /// TextBox has no way to place its cursor directly, so this replays arrow
+    /// keys from wherever the cursor currently is.
+    fn goto(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, x: usize, y: usize) -> Result<()> {
+        let arrow = |key| {
+            Event::Key(KeyEvent {
+                key,
+                modifiers: Modifiers::NONE,
+            })
+        };
+        let lines = self.lines()?;
+        let y = y.min(lines.len().saturating_sub(1));
+        let x = x.min(lines.get(y).map(|l| l.chars().count()).unwrap_or(0));
+
+        let (_, y0) = self.position();
+        let key = if y > y0 {
+            KeyCode::DownArrow
+        } else {
+            KeyCode::UpArrow
+        };
+        for _ in 0..y.abs_diff(y0) {
             self.editor
-                .read()
-                .map_err(Error::external)?
-                .buffer()
-                .read()
-                .map_err(Error::external)?
-                .join("\n"),
-        )
-        .map_err(Error::external)
+                .write()
+                .or_poisoned("editor")?
+                .update(cx, arrow(key))?;
+        }
+
+        // Moving between lines may have clamped the column.
+        let (x0, _) = self.position();
+        let key = if x > x0 {
+            KeyCode::RightArrow
+        } else {
+            KeyCode::LeftArrow
+        };
+        for _ in 0..x.abs_diff(x0) {
+            self.editor
+                .write()
+                .or_poisoned("editor")?
+                .update(cx, arrow(key))?;
+        }
+        Ok(())
+    }
+
+    fn lines(&self) -> DemoResult<Vec<String>> {
+        Ok(self
+            .editor
+            .read()
+            .or_poisoned("editor")?
+            .buffer()
+            .read()
+            .or_poisoned("buffer text")?
+            .clone())
+    }
+
+    /// Change one of the options setlocal sets.
+    ///
+    /// A buffer that is read-only for want of the lock, because another
+    /// instance has the file or it was opened with `view`, only becomes
+    /// editable by taking the lock.
+    fn set(&mut self, setting: Setting) -> DemoResult<()> {
+        match setting {
+            Setting::Wrap(wrap) => self.wrap = wrap,
+            Setting::ExpandTab(expand) => self.expand_tab = expand,
+            Setting::TabWidth(width) => self.tab_width = width,
+            Setting::Readonly(false) if self.lock.is_none() => match self.lock() {
+                // Nowhere to put a lock file; edit without one, as on open.
+                Ok(()) | Err(LockError::Io(_)) => self.readonly = false,
+                Err(LockError::Held(pid)) => {
+                    return Err(DemoError::refused(format!(
+                        "open in another instance (pid {})",
+                        pid
+                    )))
+                }
+            },
+            Setting::Readonly(readonly) => self.readonly = readonly,
+            Setting::Filetype(filetype) => self.filetype = filetype,
+        }
+        Ok(())
+    }
+
+    /// Type `key` into the TextBox. With expandtab, Tab types spaces up to
+    /// the next tab stop, counted in screen columns.
+    fn type_key(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, key: KeyEvent) -> Result<()> {
+        if !(self.expand_tab && key.modifiers == Modifiers::NONE && key.key == KeyCode::Tab) {
+            return self
+                .editor
+                .write()
+                .or_poisoned("editor")?
+                .update(cx, Event::Key(key));
+        }
+        let (x, y) = self.position();
+        let before: String = self.line(y).unwrap_or_default().chars().take(x).collect();
+        let column = unicode_column_width(&before, None);
+        let mut editor = self.editor.write().or_poisoned("editor")?;
+        for _ in 0..self.tab_width - column % self.tab_width {
+            editor.update(
+                cx,
+                Event::Key(KeyEvent {
+                    key: KeyCode::Char(' '),
+                    modifiers: Modifiers::NONE,
+                }),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// What the buffer-info popup lists.
+    fn info(&self) -> Vec<(&'static str, String)> {
+        let on = |b: bool| if b { "on" } else { "off" }.to_owned();
+        vec![
+            (
+                "filetype",
+                self.filetype.clone().unwrap_or_else(|| "none".to_owned()),
+            ),
+            ("wrap", on(self.wrap)),
+            ("expandtab", on(self.expand_tab)),
+            ("tabwidth", self.tab_width.to_string()),
+            ("readonly", on(self.readonly)),
+            ("modified", on(self.modified())),
+            ("lines", self.lines().map_or(0, |l| l.len()).to_string()),
+            (
+                "compression",
+                self.codec.map_or("none", |c| c.name()).to_owned(),
+            ),
+        ]
+    }
+
+    /// Pipe the buffer through its filetype's formatter in the background.
+    ///
+    /// The result comes back as [`Message::Formatted`]; `save` asks for the
+    /// buffer to be written once it has been applied.
+    pub fn format(&self, save: bool) -> DemoResult<()> {
+        let filetype = self
+            .filetype
+            .as_deref()
+            .ok_or_else(|| DemoError::config("unknown filetype, no formatter to run"))?;
+        let formatter = self.settings.formatter(filetype).ok_or_else(|| {
+            DemoError::config(format!("no formatter configured for {}", filetype))
+        })?;
+        let file = self.file.clone();
+        let input = self.text()?;
+        let text = input.clone();
+        runtime::spawn_ui_task(async move {
+            let result = formatter.run(&file, text).await.map(|mut formatted| {
+                // TextBox lines don't carry the trailing newline formatters add.
+                if formatted.ends_with('\n') {
+                    formatted.pop();
+                }
+                formatted
+            });
+            Message::Formatted {
+                file,
+                input,
+                result,
+                save,
+            }
+        });
+        Ok(())
     }
 }
 
@@ -151,18 +674,84 @@ impl Widget<Message, ()> for Buffer {
         cx: &mut UpdateCtx<'u, Message, ()>,
         event: Event<Message>,
     ) -> sanguine::error::Result<()> {
-        self.editor
-            .write()
-            .map_err(Error::external)?
-            .update(cx, event)
+        if let Some((x, y)) = self.pending_goto.take() {
+            self.goto(cx, x, y)?;
+        }
+        match &event {
+            Event::Key(k)
+                if self.readonly
+                    && !matches!(
+                        k.key,
+                        KeyCode::UpArrow
+                            | KeyCode::DownArrow
+                            | KeyCode::LeftArrow
+                            | KeyCode::RightArrow
+                            | KeyCode::Home
+                            | KeyCode::End
+                            | KeyCode::PageUp
+                            | KeyCode::PageDown
+                    ) =>
+            {
+                return Ok(());
+            }
+            Event::Key(k) if k.modifiers == Modifiers::CTRL && k.key == KeyCode::Char('z') => {
+                // Undo a whole-buffer replacement if nothing was typed since.
+                if let Some((before, after)) = self.replaced.take() {
+                    if self.text()? == after {
+                        self.set_text(&before)?;
+                        let (x, y) = self.pending_goto.take().unwrap();
+                        return self.goto(cx, x, y);
+                    }
+                }
+            }
+            Event::Key(k)
+                if self.settings.input.cursor_movement == CursorMovement::Visual
+                    && k.modifiers == Modifiers::NONE
+                    && matches!(k.key, KeyCode::LeftArrow | KeyCode::RightArrow) =>
+            {
+                let (x, y) = self.position();
+                let line = self.line(y).unwrap_or_default();
+                if let Some(x) = bidi::visual_step(&line, x, k.key == KeyCode::RightArrow) {
+                    return self.goto(cx, x, y);
+                }
+            }
+            Event::Key(k) if self.settings.input.dead_keys => {
+                for key in self.composer.handle(k.clone()) {
+                    self.type_key(cx, key)?;
+                }
+                return Ok(());
+            }
+            Event::Key(k) if self.expand_tab => return self.type_key(cx, k.clone()),
+            _ => {}
+        }
+        self.editor.write().or_poisoned("editor")?.update(cx, event)
     }
 
     fn cursor(&self) -> Option<(Option<usize>, usize, usize)> {
-        <TextBox as Widget<Message, ()>>::cursor(&self.editor.read().as_ref().unwrap())
+        let cursor = <TextBox as Widget<Message, ()>>::cursor(&*self.editor.read().ok()?);
+        // Show a pending jump where it will land rather than where it starts.
+        let cursor = match (cursor, self.pending_goto) {
+            (Some((z, _, _)), Some((x, y))) => Some((z, x, y)),
+            (cursor, _) => cursor,
+        };
+        let lines = self.lines().ok()?;
+        cursor.map(|(z, x, y)| {
+            let x = lines.get(y).map_or(x, |line| bidi::to_visual(line, x));
+            let (x, y) = if self.wrap {
+                let width = self.text_width.load(Ordering::Relaxed);
+                view::wrap_position(&lines, width, x, y)
+            } else {
+                (x, y)
+            };
+            (z, x + view::GUTTER, y)
+        })
     }
 
     fn constraint(&self) -> Constraint {
-        <TextBox as Widget<Message, ()>>::constraint(&self.editor.read().as_ref().unwrap())
+        // Only read, so a lock poisoned by a panic elsewhere is still good
+        // enough for this.
+        let editor = self.editor.read().unwrap_or_else(PoisonError::into_inner);
+        <TextBox as Widget<Message, ()>>::constraint(&editor)
     }
 
     fn render<'r>(
@@ -171,6 +760,11 @@ impl Widget<Message, ()> for Buffer {
         surface: &mut Surface,
     ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
         let dims = surface.dimensions();
+        // Inside the border, to the right of the sign column.
+        self.text_width.store(
+            dims.0.saturating_sub(2 + view::GUTTER).max(1),
+            Ordering::Relaxed,
+        );
         Some(vec![(
             Rect {
                 x: 0.,
@@ -180,20 +774,90 @@ impl Widget<Message, ()> for Buffer {
             },
             Arc::new(RwLock::new(Border::from_inner(
                 self.file.to_string_lossy(),
-                self.editor.clone(),
+                Arc::new(RwLock::new(EditorView {
+                    editor: self.editor.clone(),
+                    file: self.file.clone(),
+                    diagnostics: self.diagnostics.clone(),
+                    virtual_text: self.settings.diagnostics.virtual_text,
+                    wrap: self.wrap,
+                    search: self.search.clone(),
+                    theme: self.theme.clone(),
+                    preedit: self.composer.preedit(),
+                })),
             ))),
         )])
     }
 }
 
+/// How often the current file is checked for changes on disk.
+const DISK_CHECK: Duration = Duration::from_secs(2);
+
+/// Where snapshots go unless the prompt is given a path.
+const SNAPSHOT_FILE: &str = "sanguine-snapshot.ansi";
+
+/// How many closed tabs can be reopened.
+const MAX_CLOSED_TABS: usize = 20;
+
+/// What the prompt is asking for.
+enum Asking {
+    Search,
+    /// The text to replace across the project.
+    ReplaceFind,
+    /// What to replace the given text with.
+    ReplaceWith(String),
+    /// What to do about a file another instance has open.
+    Locked(PathBuf),
+    /// Where to save a snapshot of the screen.
+    Snapshot,
+    /// Options for the current buffer.
+    SetLocal,
+}
+
 struct MiniEditor {
     tabs: Vec<(String, Arc<RwLock<Buffer>>)>,
     index: usize,
     tab_layout: tui::layout::Layout,
+    settings: Arc<Settings>,
+    keymap: Arc<Keymap>,
+    diagnostics: Arc<RwLock<Diagnostics>>,
+    search: Arc<RwLock<Search>>,
+    theme: Arc<Theme>,
+    fs: Arc<dyn Filesystem>,
+    session: Session,
+    status: Option<String>,
+    /// Replaces the status bar while open.
+    prompt: Option<(Prompt, Asking)>,
+    /// Where the cursor was when the search prompt opened.
+    search_origin: (usize, usize),
+    /// Keys of a bound sequence typed so far.
+    pending: Vec<KeyEvent>,
+    pending_since: Option<Instant>,
+    which_key: Option<NodeId>,
+    /// Recently closed tabs, newest last. Buffers keep their text, so unsaved
+    /// edits come back with them.
+    closed: Vec<(String, Arc<RwLock<Buffer>>)>,
+    /// What the terminal's title was last set to.
+    title: String,
+    disk_checked: Instant,
+    autosaved: Instant,
+    /// Where to save a snapshot of the next frame.
+    snapshot: Mutex<Option<PathBuf>>,
+    /// Started with `view`: every file opens read-only, without a lock.
+    view: bool,
+    /// The size of the screen as of the last frame, to fit floats into.
+    screen: Mutex<(usize, usize)>,
+    focus_reports: FocusReports,
 }
 
 impl MiniEditor {
-    fn new() -> MiniEditor {
+    fn new(
+        settings: Arc<Settings>,
+        keymap: Arc<Keymap>,
+        diagnostics: Arc<RwLock<Diagnostics>>,
+        theme: Arc<Theme>,
+        fs: Arc<dyn Filesystem>,
+        session: Session,
+    ) -> MiniEditor {
         MiniEditor {
             tabs: vec![],
             index: 0,
@@ -203,22 +867,872 @@ impl MiniEditor {
                     [
                         tui::layout::Constraint::Length(3),
                         tui::layout::Constraint::Min(0),
+                        tui::layout::Constraint::Length(1),
                     ]
                     .as_ref(),
                 ),
+            settings,
+            keymap,
+            diagnostics,
+            search: Arc::new(RwLock::new(Search::default())),
+            theme,
+            fs,
+            session,
+            status: None,
+            prompt: None,
+            search_origin: (0, 0),
+            pending: vec![],
+            pending_since: None,
+            which_key: None,
+            closed: vec![],
+            title: String::new(),
+            disk_checked: Instant::now(),
+            autosaved: Instant::now(),
+            snapshot: Mutex::new(None),
+            view: false,
+            screen: Mutex::new((80, 24)),
+            focus_reports: FocusReports::default(),
+        }
+    }
+
+    /// Switch to the tab showing `file`, opening it if it isn't open yet.
+    fn open(&mut self, file: PathBuf) -> DemoResult<Arc<RwLock<Buffer>>> {
+        // Paths come relative from the command line, absolute from the file
+        // dialog and canonical from diagnostics.
+        let file = fs::normalize(&file);
+        if let Some(index) = self
+            .tabs
+            .iter()
+            .position(|(_, b)| b.read().map_or(false, |b| b.file == file))
+        {
+            if index != self.index && self.settings.autosave.on_tab_switch {
+                self.autosave_current();
+            }
+            self.index = index;
+        } else {
+            let mut buffer = Buffer::new(
+                file.clone(),
+                self.settings.clone(),
+                self.diagnostics.clone(),
+                self.search.clone(),
+                self.theme.clone(),
+                self.fs.clone(),
+            )?;
+            let locked = if self.view {
+                buffer.readonly = true;
+                Ok(())
+            } else {
+                buffer.lock()
+            };
+            if self.settings.autosave.on_tab_switch {
+                self.autosave_current();
+            }
+            self.add_tab(
+                file.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                buffer,
+            );
+            self.index = self.tabs.len() - 1;
+            self.locked(file, locked);
+        }
+        Ok(self.tabs[self.index].1.clone())
+    }
+
+    /// Save `buffer` if it has edits and may be written.
+    fn autosave(&mut self, buffer: &RwLock<Buffer>) -> DemoResult<()> {
+        let mut buffer = buffer.write().or_poisoned("buffer")?;
+        if buffer.readonly || !buffer.modified() {
+            return Ok(());
+        }
+        let name = buffer
+            .file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        if buffer.behind_disk() {
+            self.status = Some(format!("{} changed on disk; not autosaved", name));
+            return Ok(());
+        }
+        if let Err(e) = buffer.save() {
+            let status = error::report(&e.context("autosaving"));
+            notify::notify(format!("couldn't autosave {}", name), status.clone());
+            self.status = Some(status);
+        }
+        Ok(())
+    }
+
+    /// The editor lost focus, to another pane or a floating tool or because
+    /// the terminal itself did.
+    fn focus_lost(&mut self) {
+        if self.settings.autosave.on_focus_loss {
+            self.autosave_current();
+        }
+    }
+
+    fn autosave_current(&mut self) {
+        if let Some((_, widget)) = self.tabs.get(self.index) {
+            let widget = widget.clone();
+            if let Err(e) = self.autosave(&widget) {
+                self.status = Some(error::report(&e));
+            }
+        }
+    }
+
+    /// Save every modified buffer once `autosave.interval_secs` has passed.
+    fn autosave_due(&mut self) {
+        let Some(secs) = self.settings.autosave.interval_secs else {
+            return;
+        };
+        if self.autosaved.elapsed() < Duration::from_secs(secs) {
+            return;
+        }
+        self.autosaved = Instant::now();
+        let buffers: Vec<_> = self.tabs.iter().map(|(_, b)| b.clone()).collect();
+        for buffer in buffers {
+            if let Err(e) = self.autosave(&buffer) {
+                self.status = Some(error::report(&e));
+            }
+        }
+    }
+
+    /// Look for changes to the current tab's file on disk. Unmodified buffers
+    /// are reloaded; ones with edits ask what to do.
+    fn check_disk(&mut self, cx: &mut UpdateCtx<'_, Message, ()>) -> DemoResult<()> {
+        if self.disk_checked.elapsed() < DISK_CHECK {
+            return Ok(());
+        }
+        self.disk_checked = Instant::now();
+        let Some((name, widget)) = self.tabs.get(self.index) else {
+            return Ok(());
+        };
+        let mut buffer = widget.write().or_poisoned("buffer")?;
+        if !buffer.changed_on_disk() {
+            return Ok(());
+        }
+        if buffer.modified() {
+            buffer.conflict = true;
+            notify::notify(
+                format!("{} changed on disk", name),
+                "it has unsaved edits; pick which to keep",
+            );
+            cx.tx
+                .send(UserEvent::User(Message::DiskChanged(buffer.file.clone())))
+                .ok();
+        } else {
+            self.status = Some(match buffer.load() {
+                Ok(()) => format!("reloaded {}", name),
+                Err(e) => error::report(&e),
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply the answer to a file changing on disk under unsaved edits.
+    fn resolve(&mut self, file: &Path, theirs: &str, resolution: &Resolution) {
+        let Some(buffer) = self.buffer(file) else {
+            return;
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let resolved = buffer
+            .write()
+            .or_poisoned("buffer")
+            .and_then(|mut buffer| buffer.resolve(resolution, theirs));
+        self.status = Some(match resolved {
+            Err(e) => error::report(&e),
+            Ok(()) => match resolution {
+                Resolution::Keep => format!("kept {}; saving overwrites the file", name),
+                Resolution::Take => format!("took {} from disk, ctrl+z for the buffer", name),
+                Resolution::Merge(_) => format!("merged {}", name),
+            },
+        });
+    }
+
+    /// Run the configured build command in the background and load its output
+    /// as diagnostics.
+    fn make(&mut self) -> DemoResult<()> {
+        let command = self.settings.make_command().to_owned();
+        let cwd = std::env::current_dir().context("finding the working directory")?;
+        self.status = Some(format!("running {}", command));
+        runtime::spawn_ui_task(async move {
+            let started = Instant::now();
+            let output = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .current_dir(&cwd)
+                .output()
+                .await;
+            match output {
+                Ok(output) => {
+                    let result = if output.status.success() {
+                        "succeeded"
+                    } else {
+                        "failed"
+                    };
+                    notify::task_finished(started, format!("{} {}", command, result), "");
+                    Message::Diagnostics {
+                        source: "make".to_owned(),
+                        // Compilers are split on which stream they report to.
+                        diagnostics: quickfix::parse(
+                            &(String::from_utf8_lossy(&output.stdout).into_owned()
+                                + &String::from_utf8_lossy(&output.stderr)),
+                            "make",
+                            &cwd,
+                        ),
+                    }
+                }
+                Err(e) => Message::Status(error::report(&DemoError::tool(command, e))),
+            }
+        });
+        Ok(())
+    }
+
+    /// Show `file`, which a tutor lesson just wrote its exercise to, as if
+    /// freshly opened.
+    fn start_exercise(&mut self, file: PathBuf) -> DemoResult<()> {
+        let buffer = self.open(file)?;
+        buffer.write().or_poisoned("buffer")?.load()?;
+        self.search.write().or_poisoned("search")?.query.clear();
+        Ok(())
+    }
+
+    /// Let go of every lock, before the files go away.
+    fn unlock_all(&mut self) {
+        for (_, buffer) in self.tabs.iter().chain(&self.closed) {
+            if let Ok(mut buffer) = buffer.write() {
+                buffer.unlock();
+            }
+        }
+    }
+
+    /// The current buffer, as a tutor lesson checks it.
+    fn exercise(&self) -> Option<Exercise> {
+        let (_, widget) = self.tabs.get(self.index)?;
+        let buffer = widget.read().ok()?;
+        Some(Exercise {
+            lines: buffer.lines().ok()?,
+            cursor: buffer.position(),
+            modified: buffer.modified(),
+            search: self.search.read().ok()?.query.clone(),
+            wrap: buffer.wrap,
+        })
+    }
+
+    fn buffer_info(&self) -> Option<BufferInfo> {
+        let (name, widget) = self.tabs.get(self.index)?;
+        Some(BufferInfo::new(name.clone(), widget.read().ok()?.info()))
+    }
+
+    fn screen(&self) -> (usize, usize) {
+        self.screen.lock().map_or((80, 24), |screen| *screen)
+    }
+
+    fn buffer(&self, file: &Path) -> Option<Arc<RwLock<Buffer>>> {
+        let file = fs::normalize(file);
+        self.tabs
+            .iter()
+            .find(|(_, b)| b.read().map_or(false, |b| b.file == file))
+            .map(|(_, b)| b.clone())
+    }
+
+    /// Write reviewed replacements. Files open with unsaved edits are left
+    /// alone: reloading them would lose the edits, and saving the edits would
+    /// undo the replacement.
+    fn apply_replace(&mut self, changes: Vec<project::FileChanges>) {
+        let (open, changes): (Vec<_>, Vec<_>) = changes.into_iter().partition(|file| {
+            self.buffer(&file.path)
+                .map_or(false, |b| b.read().map_or(true, |b| b.modified()))
+        });
+        let refused = open
+            .into_iter()
+            .map(|file| (file.path, "open with unsaved edits".to_owned()))
+            .collect();
+        project::apply(changes, refused, self.fs.clone());
+    }
+
+    /// Reload buffers a project-wide replace wrote to and report how it went.
+    fn replaced(&mut self, summary: &project::ReplaceSummary) {
+        let mut kept = 0;
+        for path in &summary.written {
+            if let Some(buffer) = self.buffer(path) {
+                // Unsaved edits win; saving them will overwrite the replacement.
+                let edited = buffer
+                    .write()
+                    .or_poisoned("buffer")
+                    .map_or(true, |mut buffer| {
+                        buffer.modified() || buffer.load().is_err()
+                    });
+                kept += edited as usize;
+            }
+        }
+        let mut status = format!(
+            "replaced {} matches in {} files",
+            summary.replaced,
+            summary.written.len()
+        );
+        if let Some((path, reason)) = summary.failed.first() {
+            status += &format!(
+                ", {} failed ({}: {})",
+                summary.failed.len(),
+                path.display(),
+                reason
+            );
+        }
+        if kept > 0 {
+            status += &format!(", {} open buffers have unsaved edits", kept);
+        }
+        self.status = Some(status);
+    }
+
+    /// Apply a background formatter's output to its buffer.
+    ///
+    /// Output for text that has been edited since is dropped, along with the
+    /// save that was waiting for it, rather than undoing the edits.
+    fn formatted(&mut self, file: &Path, input: &str, result: &DemoResult<String>, save: bool) {
+        let Some(buffer) = self.buffer(file) else {
+            return;
+        };
+        let mut buffer = match buffer.write().or_poisoned("buffer") {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                self.status = Some(error::report(&e));
+                return;
+            }
+        };
+        if buffer.text().map_or(true, |text| text != input) {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            self.status = Some(if save {
+                format!("{} changed while formatting; not saved", name)
+            } else {
+                format!("{} changed while formatting; format again", name)
+            });
+            return;
+        }
+        let replaced = match result {
+            Ok(text) => buffer.replace_text(text.clone()),
+            Err(e) => {
+                self.status = Some(error::report(e));
+                Ok(())
+            }
+        };
+        if let Err(e) = replaced {
+            self.status = Some(error::report(&e));
+        }
+        // A broken formatter shouldn't stop the save itself.
+        if save {
+            if let Err(e) = buffer.save() {
+                self.status = Some(error::report(&e));
+            }
         }
     }
 
+    fn run(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, action: Action) -> Result<()> {
+        match action {
+            Action::Save => {
+                if let Some((_, widget)) = self.tabs.get(self.index) {
+                    let mut buffer = widget.write().or_poisoned("buffer")?;
+                    if buffer.readonly {
+                        self.status = Some("buffer is read-only".to_owned());
+                    } else if !self.settings.format_on_save || buffer.format(true).is_err() {
+                        // Without a formatter to wait for, save straight away.
+                        if let Err(e) = buffer.save() {
+                            self.status = Some(error::report(&e));
+                        }
+                    }
+                }
+            }
+            Action::Format => {
+                if let Some((_, widget)) = self.tabs.get(self.index) {
+                    if let Err(e) = widget.read().or_poisoned("buffer")?.format(false) {
+                        self.status = Some(error::report(&e));
+                    }
+                }
+            }
+            Action::Make => {
+                if let Err(e) = self.make() {
+                    self.status = Some(error::report(&e));
+                }
+            }
+            Action::NextTab => self.next(),
+            Action::PreviousTab => self.previous(),
+            Action::Search => {
+                if let Some((_, widget)) = self.tabs.get(self.index) {
+                    self.search_origin = widget.read().or_poisoned("buffer")?.position();
+                    self.prompt = Some((Prompt::new("/"), Asking::Search));
+                }
+            }
+            Action::SearchNext => self.search_step(cx, true)?,
+            Action::SearchPrevious => self.search_step(cx, false)?,
+            Action::CloseOtherTabs => {
+                let current = self.index;
+                let modified = self
+                    .tabs
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, (_, b))| *i != current && b.read().map_or(true, |b| b.modified()))
+                    .count();
+                // Unsaved text stays in the closed-tab history for reopening.
+                let closed = self.close_where(|i, _| i != current);
+                self.status = Some(match modified {
+                    0 => format!("closed {} tabs", closed),
+                    _ => format!(
+                        "closed {} tabs, {} with unsaved changes kept for reopening",
+                        closed, modified
+                    ),
+                });
+            }
+            Action::CloseSavedTabs => {
+                let closed = self.close_where(|_, b| !b.modified());
+                self.status = Some(format!("closed {} saved tabs", closed));
+            }
+            Action::ReopenTab => self.reopen_tab()?,
+            Action::CopyLine | Action::CopyBuffer => {
+                if let Some((_, widget)) = self.tabs.get(self.index) {
+                    let buffer = widget.read().or_poisoned("buffer")?;
+                    let text = if action == Action::CopyLine {
+                        buffer.line(buffer.position().1).unwrap_or_default()
+                    } else {
+                        buffer.text()?
+                    };
+                    self.status = Some(match osc::copy(&text) {
+                        Ok(()) => format!("copied {} characters", text.chars().count()),
+                        Err(e) => format!("couldn't copy: {}", e),
+                    });
+                }
+            }
+            Action::ClearSearch => self.search.write().or_poisoned("search")?.query.clear(),
+            Action::Snapshot => {
+                let label = format!("snapshot to (.svg for SVG) [{}]: ", SNAPSHOT_FILE);
+                self.prompt = Some((Prompt::new(label), Asking::Snapshot));
+            }
+            Action::SetLocal => {
+                if !self.tabs.is_empty() {
+                    self.prompt = Some((Prompt::new("setlocal "), Asking::SetLocal));
+                }
+            }
+            Action::ReplaceInProject => {
+                self.prompt = Some((Prompt::new("replace in project: "), Asking::ReplaceFind));
+            }
+            // Floating tools need the app to move focus.
+            Action::OpenFile
+            | Action::Diagnostics
+            | Action::KeyInspector
+            | Action::Buffers
+            | Action::BufferInfo => {
+                cx.tx.send(UserEvent::User(Message::Action(action))).ok();
+            }
+        }
+        Ok(())
+    }
+
+    fn prompt_key(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, key: KeyEvent) -> Result<()> {
+        let Some((prompt, asking)) = self.prompt.as_mut() else {
+            return Ok(());
+        };
+        let history: &[String] = match asking {
+            Asking::Search | Asking::ReplaceFind => &self.session.search_history,
+            Asking::ReplaceWith(_) | Asking::Locked(_) | Asking::Snapshot | Asking::SetLocal => &[],
+        };
+        match (prompt.handle(&key, history), asking) {
+            (PromptEvent::Submit(query), Asking::ReplaceFind) => {
+                self.session.remember_search(&query);
+                self.session.save().ok();
+                self.prompt = Some((Prompt::new("replace with: "), Asking::ReplaceWith(query)));
+            }
+            (PromptEvent::Submit(replacement), Asking::ReplaceWith(query)) => {
+                let search = Search {
+                    query: std::mem::take(query),
+                };
+                self.prompt = None;
+                self.status = Some(format!("searching for {}", search.query));
+                let root = std::env::current_dir().context("finding the working directory")?;
+                let fs = self.fs.clone();
+                runtime::spawn_ui_task(async move {
+                    let changes = tokio::task::spawn_blocking(move || {
+                        project::scan(&root, &search, &replacement, fs.as_ref())
+                    })
+                    .await
+                    .unwrap_or_default();
+                    Message::ReplacePreview(changes)
+                });
+            }
+            (PromptEvent::Submit(answer), Asking::Locked(file)) => {
+                let file = file.clone();
+                self.prompt = None;
+                match answer.trim() {
+                    "e" => {
+                        if let Some(buffer) = self.buffer(&file) {
+                            if let Err(e) = buffer.write().or_poisoned("buffer")?.take_lock() {
+                                self.status = Some(error::report(&e));
+                            }
+                        }
+                    }
+                    "c" => {
+                        self.close_where(|_, b| b.file == file);
+                    }
+                    _ => {}
+                }
+            }
+            (PromptEvent::Submit(path), Asking::Snapshot) => {
+                self.prompt = None;
+                let path = match path.trim() {
+                    "" => SNAPSHOT_FILE,
+                    path => path,
+                };
+                // Taken once the prompt is gone from the screen.
+                *self.snapshot.lock().or_poisoned("snapshot")? = Some(PathBuf::from(path));
+            }
+            (PromptEvent::Submit(input), Asking::SetLocal) => {
+                self.prompt = None;
+                match local::parse(&input) {
+                    Ok(settings) => {
+                        if let Some((_, widget)) = self.tabs.get(self.index) {
+                            let mut buffer = widget.write().or_poisoned("buffer")?;
+                            for setting in settings {
+                                if let Err(e) = buffer.set(setting) {
+                                    self.status = Some(error::report(&e.context("setlocal")));
+                                }
+                            }
+                        }
+                        cx.tx
+                            .send(UserEvent::User(Message::Action(Action::BufferInfo)))
+                            .ok();
+                    }
+                    // A typo at the prompt, not worth the error log.
+                    Err(e) => self.status = Some(e.context("setlocal").to_string()),
+                }
+            }
+            (
+                PromptEvent::Cancel,
+                Asking::ReplaceFind
+                | Asking::ReplaceWith(_)
+                | Asking::Locked(_)
+                | Asking::Snapshot
+                | Asking::SetLocal,
+            ) => {
+                self.prompt = None;
+            }
+            (event, Asking::Search) => self.search_prompt(cx, event)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn search_prompt(
+        &mut self,
+        cx: &mut UpdateCtx<'_, Message, ()>,
+        event: PromptEvent,
+    ) -> Result<()> {
+        match event {
+            PromptEvent::Edited => {
+                let input = self.prompt.as_ref().map(|(p, _)| p.input.clone());
+                self.search.write().or_poisoned("search")?.query = input.unwrap_or_default();
+                self.search_from(cx, self.search_origin, true, true)?;
+            }
+            PromptEvent::Submit(query) => {
+                self.prompt = None;
+                self.session.remember_search(&query);
+                if let Err(e) = self.session.save() {
+                    self.status = Some(format!("couldn't save search history: {}", e));
+                }
+            }
+            PromptEvent::Cancel => {
+                self.prompt = None;
+                self.search.write().or_poisoned("search")?.query.clear();
+                if let Some((_, widget)) = self.tabs.get(self.index) {
+                    let (x, y) = self.search_origin;
+                    widget.write().or_poisoned("buffer")?.goto(cx, x, y)?;
+                }
+            }
+            PromptEvent::Ignored => {}
+        }
+        Ok(())
+    }
+
+    /// Jump to the next or previous match of the current search.
+    fn search_step(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, forward: bool) -> Result<()> {
+        if let Some((_, widget)) = self.tabs.get(self.index) {
+            let from = widget.read().or_poisoned("buffer")?.position();
+            self.search_from(cx, from, forward, false)?;
+        }
+        Ok(())
+    }
+
+    fn search_from(
+        &mut self,
+        cx: &mut UpdateCtx<'_, Message, ()>,
+        from: (usize, usize),
+        forward: bool,
+        inclusive: bool,
+    ) -> Result<()> {
+        let Some((_, widget)) = self.tabs.get(self.index) else {
+            return Ok(());
+        };
+        let mut buffer = widget.write().or_poisoned("buffer")?;
+        let lines = buffer.lines()?;
+        let search = self.search.read().or_poisoned("search")?;
+        if search.query.is_empty() {
+            return Ok(());
+        }
+        let (x, y) = match search.next(&lines, from, forward, inclusive) {
+            Some(found) => {
+                self.status = Some(format!("{} matches", search.count(&lines)));
+                found
+            }
+            None => {
+                self.status = Some(format!("no match for {}", search.query));
+                from
+            }
+        };
+        buffer.goto(cx, x, y)
+    }
+
+    fn show_which_key(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, keys: &[Key]) {
+        if let Some(popup) = self.which_key.take() {
+            cx.layout.remove_node(popup);
+        }
+        let popup = WhichKey::new(&self.keymap, keys);
+        let height = popup.height();
+        self.which_key = Some(cx.layout.add_floating(
+            Supervised::new("which-key", popup),
+            Rect {
+                x: 45.0,
+                y: 5.0,
+                width: 35.,
+                height,
+            },
+        ));
+    }
+
+    fn end_sequence(&mut self, cx: &mut UpdateCtx<'_, Message, ()>) -> Vec<KeyEvent> {
+        if let Some(popup) = self.which_key.take() {
+            cx.layout.remove_node(popup);
+        }
+        self.pending_since = None;
+        std::mem::take(&mut self.pending)
+    }
+
+    fn key_event(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, key: KeyEvent) -> Result<()> {
+        if self.prompt.is_some() {
+            return self.prompt_key(cx, key);
+        }
+        // Keys finishing a composition belong to the text, not the keymap.
+        if self.composing() {
+            if let Some((_, widget)) = self.tabs.get(self.index) {
+                widget
+                    .write()
+                    .or_poisoned("buffer")?
+                    .update(cx, Event::Key(key))?;
+            }
+            return Ok(());
+        }
+        self.status = None;
+        self.key(cx, key)
+    }
+
+    /// Add `key` to the pending sequence and act on it once it means something.
+    fn key(&mut self, cx: &mut UpdateCtx<'_, Message, ()>, key: KeyEvent) -> Result<()> {
+        self.pending.push(key);
+        let keys: Vec<Key> = self.pending.iter().map(Key::from_event).collect();
+        match self.keymap.lookup(&keys) {
+            Lookup::Action(action) => {
+                self.end_sequence(cx);
+                self.run(cx, action)?;
+            }
+            Lookup::Prefix => {
+                self.pending_since = Some(Instant::now());
+                runtime::tick_after(self.keymap.timeout);
+                self.show_which_key(cx, &keys);
+            }
+            // The prefix led nowhere, so it is typed, but the key that broke
+            // it off may still start a binding of its own.
+            Lookup::None if self.pending.len() > 1 => {
+                let last = self.pending.pop().unwrap();
+                self.abandon_sequence(cx)?;
+                self.key(cx, last)?;
+            }
+            Lookup::None => self.abandon_sequence(cx)?,
+        }
+        Ok(())
+    }
+
+    /// Give up on the pending sequence and pass its keys through to the buffer.
+    fn abandon_sequence(&mut self, cx: &mut UpdateCtx<'_, Message, ()>) -> Result<()> {
+        let keys = self.end_sequence(cx);
+        if let Some((_, widget)) = self.tabs.get(self.index) {
+            let mut buffer = widget.write().or_poisoned("buffer")?;
+            for key in keys {
+                buffer.update(cx, Event::Key(key))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Name the current file, and whether it has unsaved edits, in the
+    /// terminal's title.
+    fn update_title(&mut self) {
+        if !self.settings.terminal.title {
+            return;
+        }
+        let title = match self.tabs.get(self.index) {
+            Some((name, buffer)) if buffer.read().map_or(false, |b| b.modified()) => {
+                format!("{} [+] - sanguine-demos", name)
+            }
+            Some((name, _)) => format!("{} - sanguine-demos", name),
+            None => "sanguine-demos".to_owned(),
+        };
+        if title != self.title && osc::set_title(&title).is_ok() {
+            self.title = title;
+        }
+    }
+
+    fn composing(&self) -> bool {
+        self.tabs.get(self.index).map_or(false, |(_, b)| {
+            b.read().map_or(false, |b| b.composer.composing())
+        })
+    }
+
+    fn buffer_list(&self) -> DemoResult<Vec<BufferEntry>> {
+        self.tabs
+            .iter()
+            .map(|(_, widget)| {
+                let buffer = widget.read().or_poisoned("buffer")?;
+                Ok(BufferEntry {
+                    path: buffer.file.clone(),
+                    modified: buffer.modified(),
+                    lines: buffer.lines().map_or(0, |l| l.len()),
+                })
+            })
+            .collect()
+    }
+
+    fn close_buffer(&mut self, file: &Path) {
+        let file = fs::normalize(file);
+        let Some(index) = self
+            .tabs
+            .iter()
+            .position(|(_, b)| b.read().map_or(false, |b| b.file == file))
+        else {
+            return;
+        };
+        if self.tabs[index].1.read().map_or(true, |b| b.modified()) {
+            self.status = Some(format!("{} has unsaved changes", file.display()));
+            return;
+        }
+        self.close_where(|i, _| i == index);
+    }
+
+    /// Close every tab `close` picks, keeping the current one selected if it
+    /// stays open. Returns how many were closed.
+    fn close_where(&mut self, mut close: impl FnMut(usize, &Buffer) -> bool) -> usize {
+        let current = self.tabs.get(self.index).map(|(_, b)| b.clone());
+        let mut closed = 0;
+        for (i, tab) in std::mem::take(&mut self.tabs).into_iter().enumerate() {
+            let closing = tab.1.write().map_or(false, |mut buffer| {
+                let closing = close(i, &buffer);
+                if closing {
+                    buffer.unlock();
+                }
+                closing
+            });
+            if closing {
+                self.closed.push(tab);
+                closed += 1;
+            } else {
+                self.tabs.push(tab);
+            }
+        }
+        // Forget the oldest closed tabs, but never one holding unsaved text.
+        let mut excess = self.closed.len().saturating_sub(MAX_CLOSED_TABS);
+        self.closed.retain(|(_, b)| {
+            let forget = excess > 0 && b.read().map_or(false, |b| !b.modified());
+            excess -= forget as usize;
+            !forget
+        });
+        self.index = current
+            .and_then(|c| self.tabs.iter().position(|(_, b)| Arc::ptr_eq(b, &c)))
+            .unwrap_or(self.index)
+            .min(self.tabs.len().saturating_sub(1));
+        closed
+    }
+
+    fn reopen_tab(&mut self) -> DemoResult<()> {
+        let Some((title, buffer)) = self.closed.pop() else {
+            self.status = Some("no closed tabs".to_owned());
+            return Ok(());
+        };
+        let file = buffer.read().or_poisoned("buffer")?.file.clone();
+        // Opened again some other way since; just switch to it.
+        if self.buffer(&file).is_some() {
+            self.open(file)?;
+            return Ok(());
+        }
+        let locked = {
+            let mut buffer = buffer.write().or_poisoned("buffer")?;
+            // Saved buffers pick up whatever changed on disk in the meantime.
+            if !buffer.modified() {
+                buffer.load().ok();
+            }
+            if self.view {
+                Ok(())
+            } else {
+                buffer.lock()
+            }
+        };
+        if self.settings.autosave.on_tab_switch {
+            self.autosave_current();
+        }
+        self.tabs.push((title, buffer));
+        self.index = self.tabs.len() - 1;
+        self.locked(file, locked);
+        Ok(())
+    }
+
+    /// Follow up on locking `file`: ask what to do if another instance has
+    /// it, or say why there is no lock.
+    fn locked(&mut self, file: PathBuf, result: Result<(), LockError>) {
+        match result {
+            Ok(()) => {}
+            Err(LockError::Held(pid)) => self.lock_conflict(file, pid),
+            Err(LockError::Io(e)) => {
+                let e = DemoError::from(e).context(format!("locking {}", file.display()));
+                self.status = Some(error::report(&e));
+            }
+        }
+    }
+
+    /// Ask what to do about a file that another instance is editing. It stays
+    /// read-only unless told otherwise.
+    fn lock_conflict(&mut self, file: PathBuf, pid: u32) {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let label = format!(
+            "{} is open in another instance (pid {}): [r]ead-only, [e]dit anyway, [c]lose? ",
+            name, pid
+        );
+        self.prompt = Some((Prompt::new(label), Asking::Locked(file)));
+    }
+
     fn add_tab(&mut self, title: impl Into<String>, widget: Buffer) {
         self.tabs
             .push((title.into(), Arc::new(RwLock::new(widget))));
     }
 
     pub fn next(&mut self) {
-        self.index = (self.index + 1) % self.tabs.len();
+        if self.settings.autosave.on_tab_switch {
+            self.autosave_current();
+        }
+        self.index = (self.index + 1) % self.tabs.len().max(1);
     }
 
     pub fn previous(&mut self) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        if self.settings.autosave.on_tab_switch {
+            self.autosave_current();
+        }
         if self.index > 0 {
             self.index -= 1;
         } else {
@@ -236,7 +1750,13 @@ fn ui<B: Backend, U>(f: &mut Frame<B>, app: &MiniEditor) -> tui::layout::Rect {
     let titles = app
         .tabs
         .iter()
-        .map(|(t, _)| Spans::from(vec![Span::styled(t, Style::default().fg(Color::Yellow))]))
+        .map(|(t, b)| {
+            let mut title = vec![Span::styled(t, Style::default().fg(Color::Yellow))];
+            if b.read().map_or(false, |b| b.readonly) {
+                title.push(Span::styled(" [RO]", Style::default().fg(Color::Red)));
+            }
+            Spans::from(title)
+        })
         .collect();
     let tabs = Tabs::new(titles)
         .block(Block::default().borders(Borders::ALL).title("Tabs"))
@@ -248,15 +1768,90 @@ fn ui<B: Backend, U>(f: &mut Frame<B>, app: &MiniEditor) -> tui::layout::Rect {
                 .bg(Color::Black),
         );
     f.render_widget(tabs, chunks[0]);
+    if let Some((prompt, _)) = &app.prompt {
+        f.render_widget(
+            Paragraph::new(Spans::from(vec![
+                Span::raw(prompt.label.as_str()),
+                Span::raw(prompt.input.as_str()),
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ])),
+            chunks[2],
+        );
+    } else if let Some(status) = &app.status {
+        f.render_widget(
+            Paragraph::new(status.as_str()).style(Style::default().fg(Color::Red)),
+            chunks[2],
+        );
+    }
+    let codec = app
+        .tabs
+        .get(app.index)
+        .and_then(|(_, b)| b.read().ok()?.codec);
+    if !app.pending.is_empty() {
+        let keys: Vec<Key> = app.pending.iter().map(Key::from_event).collect();
+        f.render_widget(
+            Paragraph::new(app.keymap.describe(&keys))
+                .style(Style::default().fg(Color::Yellow))
+                .alignment(Alignment::Right),
+            tui::layout::Rect {
+                width: chunks[2].width.saturating_sub(3),
+                ..chunks[2]
+            },
+        );
+    } else if let Some(codec) = codec {
+        f.render_widget(
+            Paragraph::new(codec.name())
+                .style(Style::default().fg(Color::Cyan))
+                .alignment(Alignment::Right),
+            tui::layout::Rect {
+                width: chunks[2].width.saturating_sub(3),
+                ..chunks[2]
+            },
+        );
+    }
+    if pacing::animating() {
+        let area = chunks[2];
+        f.render_widget(
+            Paragraph::new(pacing::spinner().to_string()),
+            tui::layout::Rect {
+                x: area.right().saturating_sub(2),
+                width: 1.min(area.width),
+                ..area
+            },
+        );
+    }
     chunks[1]
 }
 
 impl Widget<Message, ()> for MiniEditor {
     fn render(
         &self,
-        _: &RenderCtx<'_, Message, ()>,
+        cx: &RenderCtx<'_, Message, ()>,
         mut surface: &mut Surface,
     ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        // Taken before drawing, so the snapshot's own render of this widget
+        // doesn't take another.
+        if let Ok(mut screen) = self.screen.lock() {
+            *screen = surface.dimensions();
+        }
+        let snapshot = self.snapshot.lock().ok().and_then(|mut s| s.take());
+        if let Some(path) = snapshot {
+            let (width, height) = surface.dimensions();
+            let text = snapshot::encode(&path, &mut snapshot::compose(self, cx, width, height));
+            // Drawing has to happen here, but the write can wait.
+            runtime::spawn_ui_task(async move {
+                let written = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || std::fs::write(path, text)
+                })
+                .await;
+                Message::Status(match written {
+                    Ok(Ok(())) => format!("saved snapshot to {}", path.display()),
+                    Ok(Err(e)) => format!("couldn't save snapshot: {}", e),
+                    Err(e) => format!("couldn't save snapshot: {}", e),
+                })
+            });
+        }
         let mut rect = tui::layout::Rect::default();
         surface
             .ratatui()
@@ -279,17 +1874,9 @@ impl Widget<Message, ()> for MiniEditor {
     }
 
     fn cursor(&self) -> Option<(Option<usize>, usize, usize)> {
-        self.tabs
-            .get(self.index)
-            .map(|(_, widget)| {
-                widget
-                    .read()
-                    .as_ref()
-                    .unwrap()
-                    .cursor()
-                    .map(|(_, x, y)| (Some(2), x, y))
-            })
-            .flatten()
+        let (_, widget) = self.tabs.get(self.index)?;
+        let (_, x, y) = widget.read().ok()?.cursor()?;
+        Some((Some(2), x, y))
     }
 
     fn update<'u>(
@@ -297,33 +1884,215 @@ impl Widget<Message, ()> for MiniEditor {
         cx: &mut UpdateCtx<'u, Message, ()>,
         event: Event<Message>,
     ) -> sanguine::error::Result<()> {
+        runtime::install(&cx.tx);
+        // A sequence that timed out is typed as if it had never been bound.
+        if self
+            .pending_since
+            .map_or(false, |since| since.elapsed() >= self.keymap.timeout)
+        {
+            self.abandon_sequence(cx)?;
+        }
         match event {
-            Event::Key(k) if k.key == KeyCode::RightArrow && k.modifiers == Modifiers::SHIFT => {
-                self.next()
-            }
-            Event::Key(k) if k.key == KeyCode::LeftArrow && k.modifiers == Modifiers::SHIFT => {
-                self.previous()
-            }
-            Event::Key(k) if k.modifiers == Modifiers::CTRL && k.key == KeyCode::Char('s') => {
-                // save file
-                if let Some((_, widget)) = self.tabs.get(self.index) {
-                    let buffer = widget.write().unwrap();
-                    buffer.save()?;
+            Event::Key(k) => {
+                let (keys, focus) = self.focus_reports.handle(k);
+                if focus == Some(Focus::Lost) {
+                    self.focus_lost();
+                }
+                for k in keys {
+                    self.key_event(cx, k)?;
                 }
             }
             Event::Mouse(_) => {}
             _ => {
                 if let Some((_, widget)) = self.tabs.get(self.index) {
-                    widget.write().unwrap().update(cx, event)?;
+                    widget.write().or_poisoned("buffer")?.update(cx, event)?;
                 }
             }
         }
+        self.check_disk(cx)?;
+        self.autosave_due();
+        self.update_title();
         Ok(())
     }
 }
 
+fn diff(before: &Path, after: &Path) -> Result<()> {
+    let read = |path: &Path| std::fs::read_to_string(path).context(path.display().to_string());
+    let view = DiffView::new(
+        format!("{} -> {}", before.display(), after.display()),
+        &read(before)?,
+        &read(after)?,
+        Arc::new(Theme::new(&Settings::load()?.theme)?),
+    );
+    supervise::install();
+    let mut app = App::<(), Message>::new(Config::default())?;
+    let main = app.update_layout(move |layout| {
+        let main = layout.add_leaf_raw(Arc::new(RwLock::new(Supervised::shared(
+            "diff",
+            Arc::new(RwLock::new(view)),
+        ))));
+        layout.add_child(layout.root(), main);
+        Ok(main)
+    })?;
+    app.set_focus(main)?;
+    while app.handle_events()? {
+        app.render()?;
+    }
+    Ok(())
+}
+
+/// Walk through the editor's keys and features, one exercise at a time.
+fn tutor() -> Result<()> {
+    supervise::install();
+    let runtime = runtime::start()?;
+    let _guard = runtime.enter();
+    let mut settings = Settings::load()?;
+    // Lessons start from the defaults: autosave would pass the saving lesson
+    // by itself, and `wrap = true` the buffer options one.
+    settings.autosave = AutosaveSettings::default();
+    settings.buffer = BufferSettings::default();
+    let settings = Arc::new(settings);
+    let keymap = Arc::new(Keymap::new(&settings.keymap)?);
+    let editor = Arc::new(RwLock::new(MiniEditor::new(
+        settings.clone(),
+        keymap.clone(),
+        Arc::new(RwLock::new(Diagnostics::default())),
+        Arc::new(Theme::new(&settings.theme)?),
+        Arc::new(Compressed(Disk)),
+        Session::default(),
+    )));
+    let file = std::env::temp_dir().join(format!("sanguine-tutor-{}.txt", std::process::id()));
+    let tutor = Tutor::new(editor.clone(), keymap, file.clone())?;
+    let scratch = editor.clone();
+    // Only what the lessons use; tools that float over the editor stay shut.
+    let mut app = App::<(), Message>::new(Config::default())?.with_handler(move |_, event, _| {
+        if let Event::User(UserEvent::User(Message::Status(status))) = event {
+            editor.write().or_poisoned("editor")?.status = Some(status.clone());
+        }
+        Ok(false)
+    });
+    let main = app.update_layout(move |layout| {
+        let main = layout.add_leaf_raw(Arc::new(RwLock::new(Supervised::shared(
+            "tutor",
+            Arc::new(RwLock::new(tutor)),
+        ))));
+        layout.add_child(layout.root(), main);
+        Ok(main)
+    })?;
+    app.set_focus(main)?;
+    while app.handle_events()? {
+        app.render()?;
+    }
+    scratch.write().or_poisoned("editor")?.unlock_all();
+    std::fs::remove_file(&file).ok();
+    Ok(())
+}
+
+fn on_path(command: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    Path::new(command).is_absolute()
+        || std::env::split_paths(&paths).any(|dir| dir.join(command).is_file())
+}
+
+/// Load everything the config feeds and print what would go wrong at startup.
+fn check_config() -> Result<()> {
+    let path = Settings::path().filter(|p| p.exists());
+    let settings = Settings::load()?;
+    Keymap::new(&settings.keymap).context("[keymap]")?;
+    Theme::new(&settings.theme).context("[theme]")?;
+    let mut problems = 0;
+    for (filetype, formatter) in &settings.formatters {
+        if !on_path(&formatter.command) {
+            println!(
+                "warning: formatter `{}` for {} is not on PATH",
+                formatter.command, filetype
+            );
+            problems += 1;
+        }
+    }
+    match path {
+        Some(path) => println!("{}: ok, {} warnings", path.display(), problems),
+        None => println!("no config file, using the defaults"),
+    }
+    Ok(())
+}
+
 pub fn main() -> Result<()> {
-    let editor = Arc::new(RwLock::new(MiniEditor::new()));
+    let cli = Cli::parse();
+    match cli.command.unwrap_or(Command::Edit(cli.edit)) {
+        Command::Edit(args) => edit(args.files, args.reuse, false),
+        Command::View { files } => edit(files, false, true),
+        Command::Diff { before, after } => diff(&before, &after),
+        Command::Tutor => tutor(),
+        Command::Config(ConfigCommand::Check) => check_config(),
+        Command::Keymap(KeymapCommand::Dump) => {
+            print!("{}", Keymap::new(&Settings::load()?.keymap)?.dump());
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            let mut cli = Cli::command();
+            let name = cli.get_name().to_owned();
+            clap_complete::generate(shell, &mut cli, name, &mut std::io::stdout());
+            Ok(())
+        }
+    }
+}
+
+fn edit(files: Vec<PathBuf>, reuse: bool, readonly: bool) -> Result<()> {
+    #[cfg(unix)]
+    let _listener = if reuse {
+        if ipc::send(&files).context("handing files to the running editor")? {
+            return Ok(());
+        }
+        Some(ipc::listen().context("listening for other instances")?)
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    if reuse {
+        return Err(DemoError::refused("--reuse needs unix sockets").into());
+    }
+
+    supervise::install();
+    let runtime = runtime::start()?;
+    let _guard = runtime.enter();
+    let settings = Arc::new(Settings::load()?);
+    let diagnostics = Arc::new(RwLock::new(Diagnostics::default()));
+    let keymap = Arc::new(Keymap::new(&settings.keymap)?);
+    let theme = Arc::new(Theme::new(&settings.theme)?);
+    notify::configure(&settings.notifications);
+    // Declared before the app so the title comes back after it shuts down.
+    let _title = settings.terminal.title.then(osc::TitleGuard::push);
+    let _focus = settings
+        .autosave
+        .on_focus_loss
+        .then(focus::ReportingGuard::enable);
+    let fs: Arc<dyn Filesystem> = Arc::new(Compressed(Disk));
+    // Wake up now and then, so files changed on disk are noticed while idle.
+    tokio::spawn(async {
+        loop {
+            tokio::time::sleep(DISK_CHECK).await;
+            runtime::tick();
+        }
+    });
+    let preview = Arc::new(RwLock::new(Preview::default()));
+    let mut editor = MiniEditor::new(
+        settings.clone(),
+        keymap.clone(),
+        diagnostics.clone(),
+        theme.clone(),
+        fs.clone(),
+        Session::load(),
+    );
+    editor.view = readonly;
+    let editor = Arc::new(RwLock::new(editor));
+    for file in files {
+        editor.write().or_poisoned("editor")?.open(file)?;
+    }
+    let supervised = Supervised::shared("editor", editor.clone());
+    let crashed = supervised.crashed_handle();
     let mut app = App::<(), Message>::new(
         // The default config is fine for this example
         Config::default(),
@@ -331,30 +2100,244 @@ pub fn main() -> Result<()> {
     .with_handler({
         let editor = editor.clone();
         move |this, event, _| {
+            // The editor crashed and shows a placeholder. Closing a panel is
+            // all that still works; the rest would need the editor.
+            if crashed.get() && !matches!(event, Event::User(UserEvent::User(Message::Close(_)))) {
+                return Ok(false);
+            }
             match event {
-                Event::Key(k) if k.modifiers == Modifiers::CTRL && k.key == KeyCode::Char('o') => {
+                Event::User(UserEvent::User(Message::Action(Action::OpenFile))) => {
+                    let dialog = FileDialog::new()?;
+                    let area = preview::list_area(editor.read().or_poisoned("editor")?.screen());
+                    let float = this.update_layout(|l| {
+                        l.add_floating(Supervised::new("file dialog", dialog), area)
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                Event::User(UserEvent::User(Message::Action(Action::Diagnostics))) => {
+                    let area = preview::list_area(editor.read().or_poisoned("editor")?.screen());
+                    let float = this.update_layout(|l| {
+                        l.add_floating(
+                            Supervised::new(
+                                "diagnostics",
+                                DiagnosticsPanel::new(diagnostics.clone(), theme.clone()),
+                            ),
+                            area,
+                        )
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                Event::User(UserEvent::User(Message::Action(Action::KeyInspector))) => {
                     let float = this.update_layout(|l| {
                         l.add_floating(
-                            FileDialog::new(),
+                            Supervised::new("key inspector", KeyInspector::new(keymap.clone())),
                             Rect {
-                                x: 25.0,
-                                y: 20.0,
-                                width: 20.,
+                                x: 10.0,
+                                y: 5.0,
+                                width: 70.,
                                 height: 15.,
                             },
                         )
                     });
+                    editor.write().or_poisoned("editor")?.focus_lost();
                     this.set_focus(float)?;
                 }
+                Event::User(UserEvent::User(Message::Action(Action::Buffers))) => {
+                    let entries = editor.read().or_poisoned("editor")?.buffer_list()?;
+                    let float = this.update_layout(|l| {
+                        l.add_floating(
+                            Supervised::new("buffer picker", BufferPicker::new(entries)),
+                            Rect {
+                                x: 10.0,
+                                y: 5.0,
+                                width: 70.,
+                                height: 15.,
+                            },
+                        )
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                Event::User(UserEvent::User(Message::Action(Action::BufferInfo))) => {
+                    let Some(info) = editor.read().or_poisoned("editor")?.buffer_info() else {
+                        return Ok(false);
+                    };
+                    let height = info.height();
+                    let float = this.update_layout(|l| {
+                        l.add_floating(
+                            Supervised::new("buffer info", info),
+                            Rect {
+                                x: 10.0,
+                                y: 5.0,
+                                width: 50.,
+                                height,
+                            },
+                        )
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                // These switch tabs outside of the editor's own update, which
+                // would otherwise leave the title behind until the next key.
+                Event::User(UserEvent::User(Message::CloseBuffer(file))) => {
+                    let mut editor = editor.write().or_poisoned("editor")?;
+                    editor.close_buffer(file);
+                    editor.update_title();
+                }
                 Event::User(UserEvent::User(Message::Open(file))) => {
-                    editor.write().unwrap().add_tab(
-                        file.file_name().unwrap().to_string_lossy().to_string(),
-                        Buffer::new(file.clone())?,
-                    );
+                    let mut editor = editor.write().or_poisoned("editor")?;
+                    if let Err(e) = editor.open(file.clone()) {
+                        editor.status = Some(error::report(&e));
+                    }
+                    editor.update_title();
+                }
+                Event::User(UserEvent::User(Message::Goto(file, line, col))) => {
+                    let mut editor = editor.write().or_poisoned("editor")?;
+                    match editor.open(file.clone()) {
+                        Ok(buffer) => buffer
+                            .write()
+                            .or_poisoned("buffer")?
+                            .goto_later(*col, *line),
+                        Err(e) => editor.status = Some(error::report(&e)),
+                    }
+                    editor.update_title();
+                }
+                Event::User(UserEvent::User(Message::ReplacePreview(changes))) => {
+                    if changes.is_empty() {
+                        editor.write().or_poisoned("editor")?.status =
+                            Some("no matches".to_owned());
+                        return Ok(false);
+                    }
+                    let area = preview::list_area(editor.read().or_poisoned("editor")?.screen());
+                    let float = this.update_layout(|l| {
+                        l.add_floating(
+                            Supervised::new(
+                                "replace",
+                                ReplacePanel::new(changes.clone(), theme.clone()),
+                            ),
+                            area,
+                        )
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                Event::User(UserEvent::User(Message::ApplyReplace(changes))) => {
+                    editor
+                        .write()
+                        .or_poisoned("editor")?
+                        .apply_replace(changes.clone());
+                }
+                Event::User(UserEvent::User(Message::Replaced(summary))) => {
+                    editor.write().or_poisoned("editor")?.replaced(summary);
+                }
+                Event::User(UserEvent::User(Message::Status(status))) => {
+                    editor.write().or_poisoned("editor")?.status = Some(status.clone());
+                }
+                Event::User(UserEvent::User(Message::DiskChanged(file))) => {
+                    let Some(buffer) = editor.read().or_poisoned("editor")?.buffer(file) else {
+                        return Ok(false);
+                    };
+                    let theirs = match fs.read_to_string(file) {
+                        Ok(theirs) => theirs,
+                        Err(e) => {
+                            editor.write().or_poisoned("editor")?.status =
+                                Some(format!("couldn't read {}: {}", file.display(), e));
+                            return Ok(false);
+                        }
+                    };
+                    let panel = {
+                        let buffer = buffer.read().or_poisoned("buffer")?;
+                        ConflictPanel::new(
+                            file.clone(),
+                            &buffer.saved,
+                            &buffer.text()?,
+                            theirs,
+                            theme.clone(),
+                        )
+                    };
+                    let float = this.update_layout(|l| {
+                        l.add_floating(
+                            Supervised::new("conflict", panel),
+                            Rect {
+                                x: 5.0,
+                                y: 3.0,
+                                width: 90.,
+                                height: 25.,
+                            },
+                        )
+                    });
+                    editor.write().or_poisoned("editor")?.focus_lost();
+                    this.set_focus(float)?;
+                }
+                Event::User(UserEvent::User(Message::Resolve {
+                    file,
+                    theirs,
+                    resolution,
+                })) => {
+                    editor
+                        .write()
+                        .or_poisoned("editor")?
+                        .resolve(file, theirs, resolution);
+                }
+                Event::User(UserEvent::User(Message::Preview(target))) => {
+                    let mut state = preview.write().or_poisoned("preview")?;
+                    match target {
+                        Some((file, line)) => {
+                            state.show(fs.as_ref(), file, *line);
+                            // Floated without focus, so keys keep going to the list.
+                            if state.node.is_none() {
+                                let pane =
+                                    Supervised::new("preview", PreviewPane::new(preview.clone()));
+                                let area =
+                                    preview::area(editor.read().or_poisoned("editor")?.screen());
+                                state.node =
+                                    Some(this.update_layout(|l| l.add_floating(pane, area)));
+                            }
+                        }
+                        None => {
+                            if let Some(node) = state.node.take() {
+                                this.update_layout(|l| l.remove_node(node));
+                            }
+                        }
+                    }
+                }
+                Event::User(UserEvent::User(Message::Formatted {
+                    file,
+                    input,
+                    result,
+                    save,
+                })) => {
+                    editor
+                        .write()
+                        .or_poisoned("editor")?
+                        .formatted(file, input, result, *save);
+                }
+                Event::User(UserEvent::User(Message::Diagnostics {
+                    source,
+                    diagnostics: items,
+                })) => {
+                    let mut diagnostics = diagnostics.write().or_poisoned("diagnostics")?;
+                    diagnostics.replace_source(source, items.iter().cloned());
+                    editor.write().or_poisoned("editor")?.status = Some(format!(
+                        "{}: {} errors, {} warnings",
+                        source,
+                        diagnostics.count(Severity::Error),
+                        diagnostics.count(Severity::Warning),
+                    ));
+                }
+                Event::User(UserEvent::User(Message::Notify { summary, body })) => {
+                    notify::show(summary, body);
                 }
                 Event::User(UserEvent::User(Message::Close(float))) => {
+                    // Whatever the closed list was previewing goes with it.
+                    let pane = preview.write().or_poisoned("preview")?.node.take();
                     let node = this.update_layout(|l| {
                         l.remove_node(*float);
+                        if let Some(pane) = pane {
+                            l.remove_node(pane);
+                        }
                         l.leaves().first().copied().unwrap()
                     });
                     this.set_focus(node)?;
@@ -367,15 +2350,18 @@ pub fn main() -> Result<()> {
     });
     let main = app.update_layout(move |layout| {
         // Add the first editor to the layout
-        let main = layout.add_leaf_raw(editor);
+        let main = layout.add_leaf_raw(Arc::new(RwLock::new(supervised)));
 
         layout.add_child(layout.root(), main);
         Ok(main)
     })?;
     app.set_focus(main)?;
 
+    let mut pacer = FramePacer::new(settings.frame_rate());
     while app.handle_events()? {
-        app.render()?;
+        if pacer.frame() {
+            app.render()?;
+        }
     }
 
     Ok(())
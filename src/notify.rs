@@ -0,0 +1,60 @@
+//! Notifications for things worth knowing about while looking elsewhere, such
+//! as a long build finishing.
+//!
+//! Built with the `notifications` feature they go to the desktop; otherwise, or
+//! if that fails, the terminal bell rings. Either way they are off unless
+//! `[notifications] enabled` is set. They can be raised from any thread but
+//! are shown from the UI thread, so the bell never lands inside a half-drawn
+//! frame.
+
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::{osc, runtime, settings::NotificationSettings, Message};
+
+/// How long a task must run before its completion is worth a notification,
+/// or `None` while notifications are off.
+static AFTER: OnceLock<Option<Duration>> = OnceLock::new();
+
+pub fn configure(settings: &NotificationSettings) {
+    let after = Duration::from_secs(settings.after_secs.unwrap_or(10));
+    AFTER.set(settings.enabled.then_some(after)).ok();
+}
+
+pub fn notify(summary: impl Into<String>, body: impl Into<String>) {
+    if let Some(Some(_)) = AFTER.get() {
+        runtime::post(Message::Notify {
+            summary: summary.into(),
+            body: body.into(),
+        });
+    }
+}
+
+/// Notify that a task started at `started` finished, if it ran long enough
+/// that the user has likely moved on to something else.
+pub fn task_finished(started: Instant, summary: impl Into<String>, body: impl Into<String>) {
+    if let Some(Some(after)) = AFTER.get() {
+        if started.elapsed() >= *after {
+            notify(summary, body);
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+pub fn show(summary: &str, body: &str) {
+    let shown = notify_rust::Notification::new()
+        .appname("sanguine-demos")
+        .summary(summary)
+        .body(body)
+        .show();
+    if shown.is_err() {
+        osc::bell().ok();
+    }
+}
+
+#[cfg(not(feature = "notifications"))]
+pub fn show(_summary: &str, _body: &str) {
+    osc::bell().ok();
+}
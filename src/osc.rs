@@ -0,0 +1,45 @@
+//! Operating System Command escapes, for what the terminal does outside of the
+//! screen: its window title and the system clipboard.
+//!
+//! They pass straight through to the terminal, so copying works over SSH
+//! where there is no local clipboard to talk to.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::{self, Write};
+
+fn write(escape: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(escape.as_bytes())?;
+    out.flush()
+}
+
+pub fn set_title(title: &str) -> io::Result<()> {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    write(&format!("\x1b]2;{}\x07", title))
+}
+
+/// Saves the terminal's title on creation and puts it back on drop.
+pub struct TitleGuard(());
+
+impl TitleGuard {
+    pub fn push() -> TitleGuard {
+        // xterm's title stack; terminals without one ignore it.
+        write("\x1b[22;2t").ok();
+        TitleGuard(())
+    }
+}
+
+impl Drop for TitleGuard {
+    fn drop(&mut self) {
+        write("\x1b[23;2t").ok();
+    }
+}
+
+pub fn bell() -> io::Result<()> {
+    write("\x07")
+}
+
+/// Put `text` on the system clipboard with OSC 52.
+pub fn copy(text: &str) -> io::Result<()> {
+    write(&format!("\x1b]52;c;{}\x07", STANDARD.encode(text)))
+}
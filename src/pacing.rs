@@ -0,0 +1,110 @@
+//! Frame pacing for the main loop.
+//!
+//! The loop wakes on events and draws at most once per frame interval. While
+//! something is animating, a ticker thread feeds it a `Tick` every interval so
+//! frames keep coming; otherwise it parks and the app idles on its event
+//! channel.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::runtime;
+
+static ANIMATIONS: Mutex<usize> = Mutex::new(0);
+static ANIMATING: Condvar = Condvar::new();
+/// Set while a wakeup is queued, so bursts of redraw requests coalesce.
+static WAKE_PENDING: AtomicBool = AtomicBool::new(false);
+
+pub struct FramePacer {
+    interval: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(frame_rate: u32) -> FramePacer {
+        let interval = Duration::from_secs(1) / frame_rate.max(1);
+        std::thread::spawn(move || loop {
+            let mut animations = ANIMATIONS.lock().unwrap();
+            while *animations == 0 {
+                animations = ANIMATING.wait(animations).unwrap();
+            }
+            drop(animations);
+            std::thread::sleep(interval);
+            runtime::tick();
+        });
+        FramePacer {
+            interval,
+            last_frame: None,
+        }
+    }
+
+    /// Whether to draw after the event batch that just ran.
+    ///
+    /// Batches that arrive faster than the frame rate are folded into one
+    /// frame, with a wakeup queued so the last of them still gets drawn.
+    pub fn frame(&mut self) -> bool {
+        WAKE_PENDING.store(false, Ordering::SeqCst);
+        let now = Instant::now();
+        match self.last_frame {
+            Some(last) if now - last < self.interval => {
+                request_redraw_after(self.interval - (now - last));
+                false
+            }
+            _ => {
+                self.last_frame = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// Ask for a frame from anywhere, e.g. when background work changed state.
+pub fn request_redraw() {
+    if !WAKE_PENDING.swap(true, Ordering::SeqCst) {
+        runtime::tick();
+    }
+}
+
+fn request_redraw_after(delay: Duration) {
+    if !WAKE_PENDING.swap(true, Ordering::SeqCst) {
+        runtime::tick_after(delay);
+    }
+}
+
+/// Keeps frames coming at the target rate for as long as it is alive.
+pub struct Animation(());
+
+pub fn animate() -> Animation {
+    *ANIMATIONS.lock().unwrap() += 1;
+    ANIMATING.notify_all();
+    Animation(())
+}
+
+impl Drop for Animation {
+    fn drop(&mut self) {
+        let mut animations = ANIMATIONS.lock().unwrap();
+        *animations -= 1;
+        if *animations == 0 {
+            // One more frame to draw the final state.
+            request_redraw();
+        }
+    }
+}
+
+pub fn animating() -> bool {
+    *ANIMATIONS.lock().unwrap() > 0
+}
+
+/// The current frame of a spinner, advancing with wall-clock time.
+pub fn spinner() -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    FRAMES[(now.as_millis() / 80) as usize % FRAMES.len()]
+}
@@ -0,0 +1,241 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, Modifiers, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use crate::{selection::Selection, Message};
+
+/// Score `candidate` against a fuzzy `pattern`, or `None` if the pattern's
+/// characters don't all appear in order. Consecutive runs and matches at the
+/// start of a path component score higher.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut run = 0;
+    let mut prev = '/';
+    let mut wanted = pattern.chars().flat_map(char::to_lowercase).peekable();
+    for c in candidate.chars() {
+        let Some(&want) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(want)) {
+            wanted.next();
+            run += 1;
+            score += run;
+            if matches!(prev, '/' | '_' | '-' | '.') {
+                score += 2;
+            }
+        } else {
+            run = 0;
+        }
+        prev = c;
+    }
+    wanted.peek().is_none().then_some(score)
+}
+
+/// An open buffer as the picker lists it.
+#[derive(Debug, Clone)]
+pub struct BufferEntry {
+    pub path: PathBuf,
+    pub modified: bool,
+    pub lines: usize,
+}
+
+/// Lists the open buffers. Enter switches to one, `d` closes it and `/`
+/// starts a fuzzy filter, which Enter or Esc then leave again.
+pub struct BufferPicker {
+    entries: Vec<BufferEntry>,
+    filter: String,
+    filtering: bool,
+    selection: Selection<TableState>,
+}
+
+impl BufferPicker {
+    pub fn new(entries: Vec<BufferEntry>) -> BufferPicker {
+        BufferPicker {
+            entries,
+            filter: String::new(),
+            filtering: false,
+            selection: Selection::default(),
+        }
+    }
+
+    /// Indices into `entries` that pass the filter, best match first.
+    fn visible(&self) -> Vec<usize> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let mut scored: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let path = e.path.strip_prefix(&cwd).unwrap_or(&e.path);
+                fuzzy_score(&self.filter, &path.to_string_lossy()).map(|s| (i, s))
+            })
+            .collect();
+        if !self.filter.is_empty() {
+            scored.sort_by(|a, b| b.1.cmp(&a.1));
+        }
+        scored.into_iter().map(|(i, _)| i).collect()
+    }
+
+    fn current(&self) -> Option<usize> {
+        self.visible().get(self.selection.index()).copied()
+    }
+}
+
+impl Widget<Message, ()> for BufferPicker {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let rows: Vec<Row> = self
+            .visible()
+            .into_iter()
+            .map(|i| {
+                let entry = &self.entries[i];
+                let path = entry.path.strip_prefix(&cwd).unwrap_or(&entry.path);
+                Row::new(vec![
+                    Cell::from(Span::styled(
+                        if entry.modified { "+" } else { "" },
+                        Style::default().fg(Color::Yellow),
+                    )),
+                    Cell::from(path.display().to_string()),
+                    Cell::from(Span::styled(
+                        format!("{} lines", entry.lines),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+        let len = rows.len();
+        let title = format!("Buffers ({})", self.entries.len());
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(0), Constraint::Length(1)])
+                    .split(f.size());
+                let table = Table::new(rows)
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .widths(&[
+                        Constraint::Length(1),
+                        Constraint::Min(0),
+                        Constraint::Length(12),
+                    ])
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                self.selection.render(f, table, chunks[0], len);
+                if self.filtering || !self.filter.is_empty() {
+                    let mut filter = vec![Span::raw("/"), Span::raw(self.filter.as_str())];
+                    if self.filtering {
+                        filter.push(Span::styled(
+                            " ",
+                            Style::default().add_modifier(Modifier::REVERSED),
+                        ));
+                    }
+                    f.render_widget(Paragraph::new(Spans::from(filter)), chunks[1]);
+                }
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let Event::Key(k) = event else {
+            return Ok(());
+        };
+        let len = self.visible().len();
+        match k.key {
+            KeyCode::Enter | KeyCode::Escape if self.filtering => self.filtering = false,
+            KeyCode::Backspace if self.filtering => {
+                self.filter.pop();
+                self.selection.select(0);
+            }
+            KeyCode::Char(c)
+                if self.filtering && !k.modifiers.intersects(Modifiers::CTRL | Modifiers::ALT) =>
+            {
+                self.filter.push(c);
+                self.selection.select(0);
+            }
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Escape | KeyCode::Char('q') => {
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            KeyCode::Enter => {
+                if let Some(i) = self.current() {
+                    let path = self.entries[i].path.clone();
+                    cx.tx.send(UserEvent::User(Message::Open(path))).ok();
+                    cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(i) = self.current() {
+                    let path = self.entries[i].path.clone();
+                    cx.tx.send(UserEvent::User(Message::CloseBuffer(path))).ok();
+                    // The editor keeps modified buffers open, so keep listing them.
+                    if !self.entries[i].modified {
+                        self.entries.remove(i);
+                        self.selection.clamp(len - 1);
+                    }
+                }
+            }
+            key => {
+                self.selection.handle(key, len);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_pattern_matches_anything() {
+        assert_eq!(fuzzy_score("", "main.rs"), Some(0));
+    }
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert!(fuzzy_score("mrs", "src/main.rs").is_some());
+        assert_eq!(fuzzy_score("srm", "main.rs"), None);
+        assert_eq!(fuzzy_score("xyz", "main.rs"), None);
+    }
+
+    #[test]
+    fn matching_ignores_case() {
+        assert!(fuzzy_score("MAIN", "src/main.rs").is_some());
+        assert!(fuzzy_score("readme", "README.md").is_some());
+    }
+
+    #[test]
+    fn runs_score_higher() {
+        assert!(fuzzy_score("ma", "main") > fuzzy_score("ma", "mxa"));
+    }
+
+    #[test]
+    fn component_starts_score_higher() {
+        assert!(fuzzy_score("m", "src/m") > fuzzy_score("m", "am"));
+        assert!(fuzzy_score("r", "a_r") > fuzzy_score("r", "ar"));
+    }
+}
@@ -0,0 +1,143 @@
+//! A pane showing part of a file without taking focus, like Vim's preview
+//! window. Lists send [`Message::Preview`] as their selection moves, so what
+//! an entry points at scrolls into view while the list keeps the keyboard.
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::Event,
+    layout::{NodeId, Rect},
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use crate::{fs::Filesystem, Message};
+
+/// Rows the tabs take above floats, and the status bar below them.
+const TOP: usize = 3;
+const BOTTOM: usize = 1;
+
+/// Where the pane floats on a `width` by `height` screen: the right half.
+pub fn area((width, height): (usize, usize)) -> Rect {
+    let half = width / 2;
+    Rect {
+        x: half as f32,
+        y: TOP as f32,
+        width: width.saturating_sub(half + 1) as f32,
+        height: height.saturating_sub(TOP + BOTTOM) as f32,
+    }
+}
+
+/// Where a list that previews floats: the left half, beside the pane.
+pub fn list_area((width, height): (usize, usize)) -> Rect {
+    Rect {
+        x: 1.,
+        y: TOP as f32,
+        width: (width / 2).saturating_sub(2) as f32,
+        height: height.saturating_sub(TOP + BOTTOM) as f32,
+    }
+}
+
+#[derive(Default)]
+pub struct Preview {
+    file: Option<PathBuf>,
+    /// When `file` was last changed as of reading it, to notice saves and
+    /// changes on disk.
+    modified: Option<SystemTime>,
+    lines: Vec<String>,
+    line: usize,
+    /// The floating pane, while one is open.
+    pub node: Option<NodeId>,
+}
+
+impl Preview {
+    /// Center the preview on `line` of `file`, reading the file unless it is
+    /// the one already shown and hasn't changed since.
+    pub fn show(&mut self, fs: &dyn Filesystem, file: &Path, line: usize) {
+        let modified = fs.modified(file).ok();
+        if self.file.as_deref() != Some(file) || modified.is_none() || modified != self.modified {
+            self.lines = match fs.read_to_string(file) {
+                Ok(text) => text.lines().map(str::to_owned).collect(),
+                Err(e) => vec![e.to_string()],
+            };
+            self.file = Some(file.to_path_buf());
+            self.modified = modified;
+        }
+        self.line = line;
+    }
+}
+
+pub struct PreviewPane {
+    preview: Arc<RwLock<Preview>>,
+}
+
+impl PreviewPane {
+    pub fn new(preview: Arc<RwLock<Preview>>) -> PreviewPane {
+        PreviewPane { preview }
+    }
+}
+
+impl Widget<Message, ()> for PreviewPane {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        // Left broken by a crash elsewhere; draw nothing rather than panic
+        // again every frame.
+        let Ok(preview) = self.preview.read() else {
+            return None;
+        };
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let title = preview
+            .file
+            .as_deref()
+            .map(|f| f.strip_prefix(&cwd).unwrap_or(f).display().to_string())
+            .unwrap_or_default();
+        let (_, height) = surface.dimensions();
+        let top = preview.line.saturating_sub(height.saturating_sub(2) / 2);
+        let lines: Vec<Spans> = preview
+            .lines
+            .iter()
+            .enumerate()
+            .skip(top)
+            .map(|(n, line)| {
+                let style = if n == preview.line {
+                    Style::default().bg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                Spans::from(vec![
+                    Span::styled(format!("{:>4} ", n + 1), Style::default().fg(Color::Yellow)),
+                    Span::styled(line.as_str(), style),
+                ])
+            })
+            .collect();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let block = Block::default().borders(Borders::ALL).title(title);
+                f.render_widget(Paragraph::new(lines).block(block), f.size());
+            })
+            .ok();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        _cx: &mut UpdateCtx<'u, Message, ()>,
+        _event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        Ok(())
+    }
+}
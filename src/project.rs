@@ -0,0 +1,475 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+use crate::{
+    fs::Filesystem,
+    lock, notify, runtime,
+    search::Search,
+    selection::Selection,
+    theme::{Role, Theme},
+    Message,
+};
+
+/// Directories never worth searching.
+const SKIP_DIRS: &[&str] = &["target", "node_modules"];
+/// Files bigger than this are assumed not to be source.
+const MAX_FILE_SIZE: u64 = 1 << 20;
+
+/// One changed line.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+    pub selected: bool,
+}
+
+/// The proposed changes to one file, with the text they were computed from so
+/// edits made since the scan can be detected.
+#[derive(Debug, Clone)]
+pub struct FileChanges {
+    pub path: PathBuf,
+    original: String,
+    pub hunks: Vec<Hunk>,
+}
+
+fn replace_line(line: &str, search: &Search, replacement: &str) -> Option<String> {
+    let matches = search.matches(line);
+    if matches.is_empty() {
+        return None;
+    }
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut col = 0;
+    for start in matches {
+        out.extend(&chars[col..start]);
+        out.push_str(replacement);
+        col = start + search.match_len();
+    }
+    out.extend(&chars[col..]);
+    Some(out)
+}
+
+fn files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(t) if t.is_dir() && !SKIP_DIRS.contains(&name.as_ref()) => files(&path, out),
+            Ok(t) if t.is_file() => out.push(path),
+            _ => {}
+        }
+    }
+}
+
+/// Find every line under `root` that `search` matches and what it would
+/// become with the matches replaced. Files are read through `fs`, so
+/// compressed ones are searched as text. Binary and oversized files are
+/// skipped.
+pub fn scan(
+    root: &Path,
+    search: &Search,
+    replacement: &str,
+    fs: &dyn Filesystem,
+) -> Vec<FileChanges> {
+    let mut paths = vec![];
+    files(root, &mut paths);
+    paths.sort();
+    paths
+        .into_iter()
+        .filter(|p| p.metadata().map_or(false, |m| m.len() <= MAX_FILE_SIZE))
+        .filter_map(|path| {
+            let original = fs.read_to_string(&path).ok()?;
+            FileChanges::new(path, original, search, replacement)
+        })
+        .collect()
+}
+
+impl FileChanges {
+    /// The replacements `search` makes in `original`, if it matches at all.
+    fn new(
+        path: PathBuf,
+        original: String,
+        search: &Search,
+        replacement: &str,
+    ) -> Option<FileChanges> {
+        let hunks: Vec<Hunk> = original
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                replace_line(text, search, replacement).map(|after| Hunk {
+                    line,
+                    before: text.to_owned(),
+                    after,
+                    selected: true,
+                })
+            })
+            .collect();
+        (!hunks.is_empty()).then_some(FileChanges {
+            path,
+            original,
+            hunks,
+        })
+    }
+
+    fn selected(&self) -> usize {
+        self.hunks.iter().filter(|h| h.selected).count()
+    }
+
+    /// Apply the selected hunks, refusing if the file changed since the scan
+    /// or another instance is editing it. The file is written in place through
+    /// `fs`, the way buffers save.
+    fn apply(&self, fs: &dyn Filesystem) -> Result<usize, String> {
+        if let Some(pid) = lock::holder(&self.path) {
+            return Err(format!("open in another instance (pid {})", pid));
+        }
+        let current = fs.read_to_string(&self.path).map_err(|e| e.to_string())?;
+        if current != self.original {
+            return Err("changed on disk since the preview".to_owned());
+        }
+        // Each line keeps its own ending, CRLF or not, and the last one may
+        // have none.
+        let mut lines: Vec<String> = current.split_inclusive('\n').map(str::to_owned).collect();
+        for hunk in self.hunks.iter().filter(|h| h.selected) {
+            let line = &mut lines[hunk.line];
+            let ending = ["\r\n", "\n"]
+                .into_iter()
+                .find(|ending| line.ends_with(ending))
+                .unwrap_or_default();
+            *line = format!("{}{}", hunk.after, ending);
+        }
+        let text = lines.concat();
+        fs.write(&self.path, text.as_bytes())
+            .map_err(|e| e.to_string())?;
+        Ok(self.selected())
+    }
+}
+
+/// What applying a replacement did, for the summary and to reload buffers.
+#[derive(Debug, Default)]
+pub struct ReplaceSummary {
+    pub replaced: usize,
+    pub written: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+fn apply_all(
+    changes: Vec<FileChanges>,
+    refused: Vec<(PathBuf, String)>,
+    fs: &dyn Filesystem,
+) -> ReplaceSummary {
+    let mut summary = ReplaceSummary {
+        failed: refused,
+        ..ReplaceSummary::default()
+    };
+    for file in changes.into_iter().filter(|f| f.selected() > 0) {
+        match file.apply(fs) {
+            Ok(n) => {
+                summary.replaced += n;
+                summary.written.push(file.path);
+            }
+            Err(e) => summary.failed.push((file.path, e)),
+        }
+    }
+    summary
+}
+
+/// Write `changes` in the background and report back with
+/// [`Message::Replaced`]. `refused` are files left out before it started.
+pub fn apply(changes: Vec<FileChanges>, refused: Vec<(PathBuf, String)>, fs: Arc<dyn Filesystem>) {
+    runtime::spawn_ui_task(async move {
+        let started = Instant::now();
+        let summary = tokio::task::spawn_blocking(move || apply_all(changes, refused, fs.as_ref()))
+            .await
+            .unwrap_or_default();
+        notify::task_finished(
+            started,
+            "replace finished",
+            format!("{} files written", summary.written.len()),
+        );
+        Message::Replaced(summary)
+    });
+}
+
+enum Row {
+    File(usize),
+    Hunk(usize, usize),
+}
+
+/// Review panel for a project-wide replace. Space toggles the hunk or file
+/// under the cursor, Enter applies what is selected, Esc throws it all away.
+pub struct ReplacePanel {
+    changes: Vec<FileChanges>,
+    theme: Arc<Theme>,
+    selection: Selection<ListState>,
+}
+
+impl ReplacePanel {
+    pub fn new(changes: Vec<FileChanges>, theme: Arc<Theme>) -> ReplacePanel {
+        ReplacePanel {
+            changes,
+            theme,
+            selection: Selection::default(),
+        }
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![];
+        for (f, file) in self.changes.iter().enumerate() {
+            rows.push(Row::File(f));
+            rows.extend((0..file.hunks.len()).map(|h| Row::Hunk(f, h)));
+        }
+        rows
+    }
+
+    fn toggle(&mut self) {
+        match self.rows().get(self.selection.index()) {
+            Some(Row::File(f)) => {
+                let file = &mut self.changes[*f];
+                let select = file.selected() < file.hunks.len();
+                file.hunks.iter_mut().for_each(|h| h.selected = select);
+            }
+            Some(Row::Hunk(f, h)) => {
+                let hunk = &mut self.changes[*f].hunks[*h];
+                hunk.selected = !hunk.selected;
+            }
+            None => {}
+        }
+    }
+
+    /// Show the selected file or hunk in the preview pane.
+    fn preview(&self, cx: &mut UpdateCtx<'_, Message, ()>) {
+        let target = match self.rows().get(self.selection.index()) {
+            Some(Row::File(f)) => {
+                let file = &self.changes[*f];
+                let line = file.hunks.first().map_or(0, |h| h.line);
+                Some((file.path.clone(), line))
+            }
+            Some(Row::Hunk(f, h)) => {
+                let file = &self.changes[*f];
+                Some((file.path.clone(), file.hunks[*h].line))
+            }
+            None => None,
+        };
+        cx.tx.send(UserEvent::User(Message::Preview(target))).ok();
+    }
+}
+
+impl Widget<Message, ()> for ReplacePanel {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let check = |on: bool| if on { "[x] " } else { "[ ] " };
+        let items: Vec<ListItem> = self
+            .rows()
+            .into_iter()
+            .map(|row| match row {
+                Row::File(f) => {
+                    let file = &self.changes[f];
+                    let path = file.path.strip_prefix(&cwd).unwrap_or(&file.path);
+                    ListItem::new(Spans::from(vec![
+                        Span::raw(check(file.selected() == file.hunks.len())),
+                        Span::styled(
+                            format!(
+                                "{} ({}/{})",
+                                path.display(),
+                                file.selected(),
+                                file.hunks.len()
+                            ),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                    ]))
+                }
+                Row::Hunk(f, h) => {
+                    let hunk = &self.changes[f].hunks[h];
+                    let removed = self.theme.get(Role::DiffRemoved);
+                    let added = self.theme.get(Role::DiffAdded);
+                    ListItem::new(Text::from(vec![
+                        Spans::from(vec![
+                            Span::raw(format!("  {}{:>5} ", check(hunk.selected), hunk.line + 1)),
+                            Span::styled(
+                                format!("{} {}", removed.glyph, hunk.before.trim()),
+                                Style::default().fg(removed.color.tui()),
+                            ),
+                        ]),
+                        Spans::from(Span::styled(
+                            format!("            {} {}", added.glyph, hunk.after.trim()),
+                            Style::default().fg(added.color.tui()),
+                        )),
+                    ]))
+                }
+            })
+            .collect();
+        let total: usize = self.changes.iter().map(|f| f.selected()).sum();
+        let title = format!("Replace: {} selected (space toggle, enter apply)", total);
+        let len = items.len();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let list = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                let area = f.size();
+                self.selection.render(f, list, area, len);
+            })
+            .unwrap();
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let rows = self.rows().len();
+        match event {
+            Event::Key(k) if k.key == KeyCode::Escape || k.key == KeyCode::Char('q') => {
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            Event::Key(k) if k.key == KeyCode::Char(' ') => self.toggle(),
+            Event::Key(k) if k.key == KeyCode::Enter => {
+                // The editor checks them against open buffers first.
+                let changes = std::mem::take(&mut self.changes);
+                cx.tx
+                    .send(UserEvent::User(Message::ApplyReplace(changes)))
+                    .ok();
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            Event::Key(k) => {
+                if self.selection.handle(k.key, rows) {
+                    self.preview(cx);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::{Compressed, Memory},
+        theme::ThemeSettings,
+    };
+
+    fn changes(fs: &dyn Filesystem, name: &str, text: &str) -> FileChanges {
+        let path = Path::new("/sanguine-project-test").join(name);
+        fs.write(&path, text.as_bytes()).unwrap();
+        let search = Search {
+            query: "foo".to_owned(),
+        };
+        FileChanges::new(path, text.to_owned(), &search, "bar").unwrap()
+    }
+
+    fn read(fs: &dyn Filesystem, file: &FileChanges) -> String {
+        fs.read_to_string(&file.path).unwrap()
+    }
+
+    #[test]
+    fn hunks_leave_line_endings_out() {
+        let fs = Memory::default();
+        let file = changes(&fs, "a.txt", "a foo\r\nb\r\nfoo c\r\n");
+        let hunks: Vec<_> = file
+            .hunks
+            .iter()
+            .map(|h| (h.line, h.before.as_str(), h.after.as_str()))
+            .collect();
+        assert_eq!(hunks, vec![(0, "a foo", "a bar"), (2, "foo c", "bar c")]);
+    }
+
+    #[test]
+    fn apply_keeps_each_line_ending() {
+        let fs = Memory::default();
+        let file = changes(&fs, "a.txt", "a foo\r\nb\r\nfoo c\r\n");
+        assert_eq!(file.apply(&fs), Ok(2));
+        assert_eq!(read(&fs, &file), "a bar\r\nb\r\nbar c\r\n");
+
+        let file = changes(&fs, "b.txt", "foo\nx\r\nfoo");
+        assert_eq!(file.apply(&fs), Ok(2));
+        assert_eq!(read(&fs, &file), "bar\nx\r\nbar");
+    }
+
+    #[test]
+    fn apply_refuses_files_changed_since_the_scan() {
+        let fs = Memory::default();
+        let file = changes(&fs, "a.txt", "foo\n");
+        fs.write(&file.path, b"foo\nfoo\n").unwrap();
+        assert_eq!(
+            file.apply(&fs),
+            Err("changed on disk since the preview".to_owned())
+        );
+        assert_eq!(read(&fs, &file), "foo\nfoo\n");
+    }
+
+    #[test]
+    fn apply_writes_through_the_filesystem() {
+        let fs = Compressed(Memory::default());
+        let file = changes(&fs, "a.txt.gz", "foo\n");
+        assert_eq!(file.apply(&fs), Ok(1));
+        assert_eq!(read(&fs, &file), "bar\n");
+        assert_eq!(fs.0.read(&file.path).unwrap()[..2], [0x1f, 0x8b]);
+    }
+
+    #[test]
+    fn toggling_picks_the_hunks_applied() {
+        let fs = Memory::default();
+        let first = changes(&fs, "a.txt", "foo 1\nfoo 2\n");
+        let second = changes(&fs, "b.txt", "foo 3\n");
+        let theme = Arc::new(Theme::new(&ThemeSettings::default()).unwrap());
+        let mut panel = ReplacePanel::new(vec![first, second], theme);
+        // Rows: a.txt, its two hunks, b.txt, its hunk.
+        panel.toggle();
+        assert_eq!(panel.changes[0].selected(), 0);
+        panel.toggle();
+        assert_eq!(panel.changes[0].selected(), 2);
+        panel.selection.select(1);
+        panel.toggle();
+        panel.selection.select(3);
+        panel.toggle();
+        assert_eq!(panel.changes[1].selected(), 0);
+
+        let summary = apply_all(
+            std::mem::take(&mut panel.changes),
+            vec![(PathBuf::from("c.txt"), "open with unsaved edits".to_owned())],
+            &fs,
+        );
+        assert_eq!(summary.replaced, 1);
+        assert_eq!(
+            summary.written,
+            vec![Path::new("/sanguine-project-test/a.txt")]
+        );
+        assert_eq!(summary.failed.len(), 1);
+        let a = fs.read(Path::new("/sanguine-project-test/a.txt")).unwrap();
+        assert_eq!(a, b"foo 1\nbar 2\n");
+        let b = fs.read(Path::new("/sanguine-project-test/b.txt")).unwrap();
+        assert_eq!(b, b"foo 3\n");
+    }
+}
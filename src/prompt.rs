@@ -0,0 +1,74 @@
+use sanguine::event::{KeyCode, KeyEvent, Modifiers};
+
+pub enum PromptEvent {
+    /// The input changed.
+    Edited,
+    Submit(String),
+    Cancel,
+    Ignored,
+}
+
+/// A one-line input shown in place of the status bar.
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+    /// Position in the history while browsing it with Up/Down.
+    browsing: Option<usize>,
+    /// What was typed before browsing the history started.
+    draft: String,
+}
+
+impl Prompt {
+    pub fn new(label: impl Into<String>) -> Prompt {
+        Prompt {
+            label: label.into(),
+            input: String::new(),
+            browsing: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Edit the input with `key`. `history` is oldest first.
+    pub fn handle(&mut self, key: &KeyEvent, history: &[String]) -> PromptEvent {
+        match key.key {
+            KeyCode::Enter => PromptEvent::Submit(std::mem::take(&mut self.input)),
+            KeyCode::Escape => PromptEvent::Cancel,
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.browsing = None;
+                PromptEvent::Edited
+            }
+            KeyCode::UpArrow if !history.is_empty() => {
+                let index = match self.browsing {
+                    None => {
+                        self.draft = self.input.clone();
+                        history.len() - 1
+                    }
+                    Some(index) => index.saturating_sub(1),
+                };
+                self.browsing = Some(index);
+                self.input = history[index].clone();
+                PromptEvent::Edited
+            }
+            KeyCode::DownArrow => match self.browsing {
+                Some(index) if index + 1 < history.len() => {
+                    self.browsing = Some(index + 1);
+                    self.input = history[index + 1].clone();
+                    PromptEvent::Edited
+                }
+                Some(_) => {
+                    self.browsing = None;
+                    self.input = std::mem::take(&mut self.draft);
+                    PromptEvent::Edited
+                }
+                None => PromptEvent::Ignored,
+            },
+            KeyCode::Char(c) if !key.modifiers.intersects(Modifiers::CTRL | Modifiers::ALT) => {
+                self.input.push(c);
+                self.browsing = None;
+                PromptEvent::Edited
+            }
+            _ => PromptEvent::Ignored,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::{Diagnostic, Severity};
+
+/// Parse compiler-style `path:line[:col]: [severity:] message` lines, as
+/// produced by gcc, clang and `cargo check --message-format short`.
+///
+/// Relative paths are resolved against `cwd`. Lines that don't match are
+/// skipped, so the whole output of a build can be passed in.
+pub fn parse(output: &str, source: &str, cwd: &Path) -> Vec<(PathBuf, Diagnostic)> {
+    output
+        .lines()
+        .filter_map(|line| parse_line(line, source, cwd))
+        .collect()
+}
+
+fn parse_line(line: &str, source: &str, cwd: &Path) -> Option<(PathBuf, Diagnostic)> {
+    let mut parts = line.splitn(3, ':');
+    let path = parts.next()?.trim();
+    let line_no: usize = parts.next()?.trim().parse().ok()?;
+    let rest = parts.next()?;
+    if path.is_empty() {
+        return None;
+    }
+
+    let (col, rest) = match rest.split_once(':') {
+        Some((col, rest)) if col.trim().parse::<usize>().is_ok() => {
+            (col.trim().parse::<usize>().unwrap(), rest)
+        }
+        _ => (1, rest),
+    };
+
+    // `error[E0425]: ...` -> `error`
+    let (severity, message) = match rest.split_once(':') {
+        Some((sev, message)) => {
+            let sev = sev.split('[').next().unwrap_or(sev);
+            match Severity::parse(sev) {
+                Some(severity) => (severity, message),
+                None => (Severity::Error, rest),
+            }
+        }
+        None => (Severity::Error, rest),
+    };
+
+    let start = (line_no.saturating_sub(1), col.saturating_sub(1));
+    Some((
+        cwd.join(path),
+        Diagnostic {
+            source: source.to_owned(),
+            severity,
+            start,
+            end: (start.0, start.1 + 1),
+            message: message.trim().to_owned(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_column_and_severity() {
+        let found = parse(
+            "src/main.rs:10:5: warning: unused variable: `x`",
+            "cargo",
+            Path::new("/project"),
+        );
+        assert_eq!(found.len(), 1);
+        let (path, d) = &found[0];
+        assert_eq!(path, Path::new("/project/src/main.rs"));
+        assert_eq!(d.source, "cargo");
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!((d.start, d.end), ((9, 4), (9, 5)));
+        assert_eq!(d.message, "unused variable: `x`");
+    }
+
+    #[test]
+    fn column_is_optional() {
+        let found = parse(
+            "lib.rs:3: error: missing semicolon",
+            "make",
+            Path::new("/p"),
+        );
+        assert_eq!(found[0].1.start, (2, 0));
+        assert_eq!(found[0].1.message, "missing semicolon");
+    }
+
+    #[test]
+    fn error_codes_are_dropped_from_the_severity() {
+        let found = parse(
+            "src/lib.rs:1:1: error[E0425]: cannot find value `y`",
+            "cargo",
+            Path::new("/p"),
+        );
+        assert_eq!(found[0].1.severity, Severity::Error);
+        assert_eq!(found[0].1.message, "cannot find value `y`");
+    }
+
+    #[test]
+    fn unknown_severity_is_an_error_with_the_whole_message() {
+        let found = parse("main.c:1:2: odd: thing", "make", Path::new("/p"));
+        assert_eq!(found[0].1.severity, Severity::Error);
+        assert_eq!(found[0].1.message, "odd: thing");
+    }
+
+    #[test]
+    fn absolute_paths_are_kept() {
+        let found = parse("/src/a.c:1:1: error: x", "make", Path::new("/p"));
+        assert_eq!(found[0].0, Path::new("/src/a.c"));
+    }
+
+    #[test]
+    fn other_lines_are_skipped() {
+        let output = "   Compiling demo v0.1.0\n\
+                      a.rs:2:1: warning: dead code\n\
+                      error: could not compile `demo`\n\
+                      :1:1: error: no file\n";
+        let found = parse(output, "cargo", Path::new("/p"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, Path::new("/p/a.rs"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Glue between the tokio runtime and sanguine's event loop.
+//!
+//! sanguine only hands out its event sender inside widget updates, so the
+//! editor installs it here on every update. Messages produced before that
+//! happens are queued and delivered once it does, and anything that needs the
+//! sender to work at all can wait for it with [`when_installed`].
+
+use sanguine::event::UserEvent;
+use std::{
+    future::Future,
+    sync::{mpsc::Sender, Mutex},
+    time::Duration,
+};
+use tokio::{runtime::Runtime, task::JoinHandle};
+
+use crate::{
+    error::{Context, DemoResult},
+    pacing, Message,
+};
+
+static SENDER: Mutex<Option<Sender<UserEvent<Message>>>> = Mutex::new(None);
+static PENDING: Mutex<Vec<Message>> = Mutex::new(Vec::new());
+static READY: Mutex<Vec<Box<dyn FnOnce() + Send>>> = Mutex::new(Vec::new());
+
+pub fn start() -> DemoResult<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("starting the async runtime")
+}
+
+/// Remember sanguine's event sender and flush anything sent before it was known.
+pub fn install(tx: &Sender<UserEvent<Message>>) {
+    let ready = {
+        let mut sender = SENDER.lock().unwrap();
+        if sender.is_some() {
+            return;
+        }
+        for message in PENDING.lock().unwrap().drain(..) {
+            tx.send(UserEvent::User(message)).ok();
+        }
+        *sender = Some(tx.clone());
+        std::mem::take(&mut *READY.lock().unwrap())
+    };
+    for f in ready {
+        f();
+    }
+}
+
+/// Run `f` once the event sender is installed, or now if it already is.
+pub fn when_installed(f: impl FnOnce() + Send + 'static) {
+    let sender = SENDER.lock().unwrap();
+    if sender.is_none() {
+        READY.lock().unwrap().push(Box::new(f));
+        return;
+    }
+    drop(sender);
+    f();
+}
+
+/// Deliver `message` to the app from any thread.
+pub fn post(message: Message) {
+    match &*SENDER.lock().unwrap() {
+        Some(tx) => {
+            tx.send(UserEvent::User(message)).ok();
+        }
+        None => PENDING.lock().unwrap().push(message),
+    }
+}
+
+/// Wake the event loop without a message, e.g. to draw another frame.
+pub fn tick() {
+    if let Some(tx) = &*SENDER.lock().unwrap() {
+        tx.send(UserEvent::Tick).ok();
+    }
+}
+
+/// Like [`tick`], once `delay` has passed.
+pub fn tick_after(delay: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        tick();
+    });
+}
+
+/// Run `task` on the runtime and deliver the message it resolves to.
+///
+/// The status bar spinner turns while any task is running.
+pub fn spawn_ui_task<F>(task: F) -> JoinHandle<()>
+where
+    F: Future<Output = Message> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let _busy = pacing::animate();
+        post(task.await)
+    })
+}
@@ -0,0 +1,137 @@
+/// The query highlighted in every buffer. Empty when nothing is searched for.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+}
+
+impl Search {
+    /// Smart case: the query only matches case-sensitively if it has capitals.
+    fn fold(&self, c: char) -> char {
+        if self.query.chars().any(char::is_uppercase) {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    }
+
+    /// Columns (in chars) where the query starts in `line`.
+    pub fn matches(&self, line: &str) -> Vec<usize> {
+        if self.query.is_empty() {
+            return vec![];
+        }
+        let query: Vec<char> = self.query.chars().map(|c| self.fold(c)).collect();
+        let line: Vec<char> = line.chars().map(|c| self.fold(c)).collect();
+        let mut found = vec![];
+        let mut col = 0;
+        while col + query.len() <= line.len() {
+            if line[col..col + query.len()] == query[..] {
+                found.push(col);
+                col += query.len();
+            } else {
+                col += 1;
+            }
+        }
+        found
+    }
+
+    /// Length of a match, in chars.
+    pub fn match_len(&self) -> usize {
+        self.query.chars().count()
+    }
+
+    /// The first match after `(x, y)`, or before it when searching backwards,
+    /// wrapping around the buffer. Returns `(column, line)`.
+    pub fn next(
+        &self,
+        lines: &[String],
+        (x, y): (usize, usize),
+        forward: bool,
+        inclusive: bool,
+    ) -> Option<(usize, usize)> {
+        let all: Vec<(usize, usize)> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line, text)| self.matches(text).into_iter().map(move |col| (line, col)))
+            .collect();
+        let here = (y, x);
+        let found = if forward {
+            all.iter()
+                .find(|&&m| if inclusive { m >= here } else { m > here })
+                .or_else(|| all.first())
+        } else {
+            all.iter()
+                .rev()
+                .find(|&&m| if inclusive { m <= here } else { m < here })
+                .or_else(|| all.last())
+        };
+        found.map(|&(line, col)| (col, line))
+    }
+
+    pub fn count(&self, lines: &[String]) -> usize {
+        lines.iter().map(|l| self.matches(l).len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(query: &str) -> Search {
+        Search {
+            query: query.to_owned(),
+        }
+    }
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|&l| l.to_owned()).collect()
+    }
+
+    #[test]
+    fn lowercase_queries_ignore_case() {
+        assert_eq!(search("foo").matches("Foo foo FOO"), vec![0, 4, 8]);
+    }
+
+    #[test]
+    fn capitals_make_the_query_case_sensitive() {
+        assert_eq!(search("Foo").matches("Foo foo FOO"), vec![0]);
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        assert_eq!(search("aa").matches("aaaaa"), vec![0, 2]);
+    }
+
+    #[test]
+    fn columns_count_chars_not_bytes() {
+        let search = search("x");
+        assert_eq!(search.matches("ééx"), vec![2]);
+        assert_eq!(search.match_len(), 1);
+    }
+
+    #[test]
+    fn empty_query_matches_nothing() {
+        assert!(search("").matches("anything").is_empty());
+        assert_eq!(search("").next(&lines(&["a"]), (0, 0), true, true), None);
+    }
+
+    #[test]
+    fn next_wraps_around_the_buffer() {
+        let lines = lines(&["ab", "xab"]);
+        let search = search("ab");
+        assert_eq!(search.next(&lines, (0, 0), true, false), Some((1, 1)));
+        assert_eq!(search.next(&lines, (1, 1), true, false), Some((0, 0)));
+        assert_eq!(search.next(&lines, (0, 0), false, false), Some((1, 1)));
+    }
+
+    #[test]
+    fn inclusive_next_stays_on_a_match_at_the_cursor() {
+        let lines = lines(&["ab", "xab"]);
+        assert_eq!(search("ab").next(&lines, (0, 0), true, true), Some((0, 0)));
+        assert_eq!(search("ab").next(&lines, (1, 1), false, true), Some((1, 1)));
+    }
+
+    #[test]
+    fn count_adds_up_every_line() {
+        assert_eq!(search("a").count(&lines(&["a a", "", "bab"])), 3);
+    }
+}
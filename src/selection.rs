@@ -0,0 +1,86 @@
+//! Glue between sanguine widgets and ratatui's stateful ones.
+//!
+//! ratatui's `List` and `Table` draw from a `ListState` or `TableState`, which
+//! they also scroll, while sanguine renders through `&self` and only lets a
+//! widget change in `update`. A widget keeps a [`Selection`] instead: keys move
+//! it in `update`, and [`Selection::render`] copies the selection into the
+//! ratatui state and keeps whatever scrolling ratatui did to it for the next
+//! frame, so the list doesn't jump back to the top each time it is drawn.
+
+use ratatui::{
+    layout::Rect,
+    widgets::{ListState, StatefulWidget, TableState},
+    Frame,
+};
+use sanguine::{bridge::BridgeInner, event::KeyCode};
+use std::sync::{Mutex, PoisonError};
+
+/// How far PageUp and PageDown move.
+const PAGE: usize = 10;
+
+/// ratatui states holding a selected row.
+pub trait Selectable: Default {
+    fn select(&mut self, index: Option<usize>);
+}
+
+impl Selectable for ListState {
+    fn select(&mut self, index: Option<usize>) {
+        ListState::select(self, index)
+    }
+}
+
+impl Selectable for TableState {
+    fn select(&mut self, index: Option<usize>) {
+        TableState::select(self, index)
+    }
+}
+
+#[derive(Default)]
+pub struct Selection<S> {
+    index: usize,
+    state: Mutex<S>,
+}
+
+impl<S: Selectable> Selection<S> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    /// Keep the selection inside a list that shrank to `len` rows.
+    pub fn clamp(&mut self, len: usize) {
+        self.index = self.index.min(len.saturating_sub(1));
+    }
+
+    /// Move the selection with j/k, the arrows, PageUp/PageDown and
+    /// Home/End, in a list of `len` rows. Returns whether it moved.
+    pub fn handle(&mut self, key: KeyCode, len: usize) -> bool {
+        let last = len.saturating_sub(1);
+        let index = match key {
+            KeyCode::DownArrow | KeyCode::Char('j') => (self.index + 1).min(last),
+            KeyCode::UpArrow | KeyCode::Char('k') => self.index.saturating_sub(1),
+            KeyCode::PageDown => (self.index + PAGE).min(last),
+            KeyCode::PageUp => self.index.saturating_sub(PAGE),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            _ => return false,
+        };
+        let moved = index != self.index;
+        self.index = index;
+        moved
+    }
+
+    /// Draw `widget`, a list of `len` rows, with the selection highlighted.
+    pub fn render<W>(&self, f: &mut Frame<BridgeInner>, widget: W, area: Rect, len: usize)
+    where
+        W: StatefulWidget<State = S>,
+    {
+        // Only scroll state: after a panic mid-draw it is still fine to use.
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.select((len > 0).then_some(self.index.min(len - 1)));
+        f.render_stateful_widget(widget, area, &mut *state);
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::error::{Context, DemoError, DemoResult};
+
+/// State kept between runs, in `$XDG_STATE_HOME/sanguine-demos/session.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Submitted searches, oldest first.
+    pub search_history: Vec<String>,
+    /// Whether [`Session::save`] writes the session file. Only a session from
+    /// [`Session::load`] does, so a scratch one like the tutor's leaves the
+    /// user's history alone.
+    #[serde(skip)]
+    kept: bool,
+}
+
+impl Session {
+    const MAX_HISTORY: usize = 100;
+
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("state"))
+            })?;
+        Some(base.join("sanguine-demos").join("session.toml"))
+    }
+
+    /// Load the last session. A missing or unreadable file starts a fresh one;
+    /// losing history isn't worth refusing to start over.
+    pub fn load() -> Session {
+        let session: Session = Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default();
+        Session {
+            kept: true,
+            ..session
+        }
+    }
+
+    pub fn save(&self) -> DemoResult<()> {
+        if !self.kept {
+            return Ok(());
+        }
+        let path = Self::path()
+            .ok_or_else(|| DemoError::config("no state directory, set XDG_STATE_HOME or HOME"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(dir.display().to_string())?;
+        }
+        let text = toml::to_string(self).map_err(|e| DemoError::config(e.to_string()))?;
+        std::fs::write(&path, text).context(path.display().to_string())
+    }
+
+    /// Record a submitted search, moving repeats to the end.
+    pub fn remember_search(&mut self, query: &str) {
+        if query.is_empty() {
+            return;
+        }
+        self.search_history.retain(|q| q != query);
+        self.search_history.push(query.to_owned());
+        let excess = self.search_history.len().saturating_sub(Self::MAX_HISTORY);
+        self.search_history.drain(..excess);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    bidi::CursorMovement,
+    error::{Context, DemoError, DemoResult},
+    format::Formatter,
+    keymap::KeymapSettings,
+    theme::ThemeSettings,
+};
+
+/// User settings, read from `$XDG_CONFIG_HOME/sanguine-demos/config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Run the buffer's formatter before every save.
+    pub format_on_save: bool,
+    pub autosave: AutosaveSettings,
+    /// Defaults for the options each buffer can change with setlocal.
+    pub buffer: BufferSettings,
+    /// Formatters keyed by filetype, overriding the built-in ones.
+    pub formatters: HashMap<String, Formatter>,
+    /// Shell command run by the make action. Its output is parsed for
+    /// `path:line:col: severity: message` diagnostics.
+    pub make_command: Option<String>,
+    pub diagnostics: DiagnosticSettings,
+    /// Upper bound on frames drawn per second. Defaults to 60.
+    pub frame_rate: Option<u32>,
+    pub keymap: KeymapSettings,
+    pub theme: ThemeSettings,
+    pub input: InputSettings,
+    pub terminal: TerminalSettings,
+    pub notifications: NotificationSettings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagnosticSettings {
+    /// Show the message after the end of the line it refers to.
+    pub virtual_text: bool,
+}
+
+impl Default for DiagnosticSettings {
+    fn default() -> Self {
+        DiagnosticSettings { virtual_text: true }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BufferSettings {
+    /// Wrap lines too long for the window instead of cutting them off.
+    pub wrap: bool,
+    /// Make Tab indent to the next tab stop with spaces.
+    pub expand_tab: bool,
+    /// Columns between tab stops. Defaults to 4.
+    pub tab_width: Option<usize>,
+}
+
+/// When to save buffers without being asked. Read-only buffers are never
+/// saved, and autosaves don't run the formatter.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutosaveSettings {
+    /// Save the current buffer before switching to another tab.
+    pub on_tab_switch: bool,
+    /// Save the current buffer when another pane or a floating tool takes
+    /// focus from it, or the terminal window loses focus.
+    pub on_focus_loss: bool,
+    /// Save every modified buffer this often, in seconds. Off unless set.
+    pub interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Background tasks that finish sooner than this many seconds don't
+    /// notify. Defaults to 10.
+    pub after_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalSettings {
+    /// Show the current file in the terminal's title.
+    pub title: bool,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        TerminalSettings { title: true }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputSettings {
+    /// Compose dead keys like `´` with the next key instead of typing them.
+    pub dead_keys: bool,
+    /// `logical` or `visual` movement through right-to-left text.
+    pub cursor_movement: CursorMovement,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {
+            dead_keys: true,
+            cursor_movement: CursorMovement::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("sanguine-demos").join("config.toml"))
+    }
+
+    /// Load the settings file, falling back to the defaults if there isn't one.
+    pub fn load() -> DemoResult<Settings> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Settings::default());
+        };
+        let text = std::fs::read_to_string(&path).context(path.display().to_string())?;
+        toml::from_str(&text)
+            .map_err(|e| DemoError::config(e.to_string()).context(path.display().to_string()))
+    }
+
+    pub fn frame_rate(&self) -> u32 {
+        self.frame_rate.unwrap_or(60).max(1)
+    }
+
+    pub fn tab_width(&self) -> usize {
+        self.buffer.tab_width.unwrap_or(4).max(1)
+    }
+
+    pub fn make_command(&self) -> &str {
+        self.make_command
+            .as_deref()
+            .unwrap_or("cargo check --message-format short")
+    }
+
+    pub fn formatter(&self, filetype: &str) -> Option<Formatter> {
+        self.formatters
+            .get(filetype)
+            .cloned()
+            .or_else(|| Formatter::builtin(filetype))
+    }
+}
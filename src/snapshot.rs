@@ -0,0 +1,330 @@
+//! Snapshots of the screen for documentation, as ANSI text or SVG.
+//!
+//! sanguine composes each frame internally, so a snapshot draws the widget
+//! tree again into a surface of its own the same way: each widget renders,
+//! then the children it returns are rendered into their rects over it.
+//! Floating panels live in the app's layout rather than under the editor and
+//! are not part of it.
+//!
+//! The ANSI form is plain text with SGR escapes, stable enough to diff between
+//! runs; `cat` replays it in a terminal.
+
+use sanguine::{layout::Rect, surface::Surface, RenderCtx, Widget};
+use std::{fmt::Write, path::Path};
+use termwiz::{
+    cell::{Cell, CellAttributes, Intensity, Underline},
+    color::ColorAttribute,
+};
+
+use crate::Message;
+
+/// Cell size in the SVG, for a 14px monospace font.
+const CELL_WIDTH: f32 = 8.4;
+const CELL_HEIGHT: f32 = 17.;
+/// Colors for cells that use the terminal's defaults.
+const DEFAULT_FG: (u8, u8, u8) = (0xd0, 0xd0, 0xd0);
+const DEFAULT_BG: (u8, u8, u8) = (0x1c, 0x1c, 0x1c);
+
+/// Render `widget` and everything under it into a `width` x `height` surface.
+pub fn compose(
+    widget: &dyn Widget<Message, ()>,
+    cx: &RenderCtx<'_, Message, ()>,
+    width: usize,
+    height: usize,
+) -> Surface {
+    let mut surface = Surface::new(width, height);
+    let children = widget.render(cx, &mut surface);
+    for (rect, child) in children.unwrap_or_default() {
+        let Rect {
+            x,
+            y,
+            width: w,
+            height: h,
+        } = rect;
+        // Left broken by a panic; the supervisor shows that on screen.
+        let Ok(child) = child.read() else {
+            continue;
+        };
+        let child = compose(&*child, cx, w as usize, h as usize);
+        surface.draw_from_screen(&child, x as usize, y as usize);
+    }
+    surface
+}
+
+/// `surface` as the file at `path` should hold it: SVG if it ends in `.svg`,
+/// ANSI text otherwise.
+pub fn encode(path: &Path, surface: &mut Surface) -> String {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => svg(surface),
+        _ => ansi(surface),
+    }
+}
+
+/// The rows of `surface`, without the padding cells after wide characters.
+fn rows(surface: &mut Surface) -> Vec<Vec<(usize, Cell)>> {
+    surface
+        .screen_cells()
+        .iter()
+        .map(|row| {
+            let mut cells = vec![];
+            let mut x = 0;
+            while let Some(cell) = row.get(x) {
+                cells.push((x, cell.clone()));
+                x += cell.width().max(1);
+            }
+            cells
+        })
+        .collect()
+}
+
+fn sgr_color(out: &mut String, color: ColorAttribute, base: u8) {
+    // `base` is 30 for the foreground and 40 for the background.
+    match color {
+        ColorAttribute::Default => {}
+        ColorAttribute::PaletteIndex(i) if i < 8 => write!(out, ";{}", base + i).unwrap(),
+        ColorAttribute::PaletteIndex(i) if i < 16 => write!(out, ";{}", base + 60 + i - 8).unwrap(),
+        ColorAttribute::PaletteIndex(i) => write!(out, ";{};5;{}", base + 8, i).unwrap(),
+        ColorAttribute::TrueColorWithPaletteFallback(c, _)
+        | ColorAttribute::TrueColorWithDefaultFallback(c) => {
+            let (r, g, b, _) = c.to_srgb_u8();
+            write!(out, ";{};2;{};{};{}", base + 8, r, g, b).unwrap()
+        }
+    }
+}
+
+fn sgr(attrs: &CellAttributes) -> String {
+    let mut out = String::from("\x1b[0");
+    match attrs.intensity() {
+        Intensity::Bold => out += ";1",
+        Intensity::Half => out += ";2",
+        Intensity::Normal => {}
+    }
+    if attrs.italic() {
+        out += ";3";
+    }
+    match attrs.underline() {
+        Underline::None => {}
+        Underline::Single => out += ";4",
+        Underline::Double => out += ";21",
+        Underline::Curly => out += ";4:3",
+        Underline::Dotted => out += ";4:4",
+        Underline::Dashed => out += ";4:5",
+    }
+    if attrs.reverse() {
+        out += ";7";
+    }
+    if attrs.strikethrough() {
+        out += ";9";
+    }
+    sgr_color(&mut out, attrs.foreground(), 30);
+    sgr_color(&mut out, attrs.background(), 40);
+    out + "m"
+}
+
+pub fn ansi(surface: &mut Surface) -> String {
+    let mut out = String::new();
+    for row in rows(surface) {
+        let mut current = None;
+        for (_, cell) in row {
+            if current.as_ref() != Some(cell.attrs()) {
+                out += &sgr(cell.attrs());
+                current = Some(cell.attrs().clone());
+            }
+            out += cell.str();
+        }
+        out += "\x1b[0m\n";
+    }
+    out
+}
+
+/// The xterm palette.
+fn palette(i: u8) -> (u8, u8, u8) {
+    const BASE: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+    match i {
+        0..=15 => BASE[i as usize],
+        16..=231 => {
+            let i = i - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (i - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+fn hex(color: ColorAttribute, default: (u8, u8, u8)) -> String {
+    let (r, g, b) = match color {
+        ColorAttribute::Default => default,
+        ColorAttribute::PaletteIndex(i) => palette(i),
+        ColorAttribute::TrueColorWithPaletteFallback(c, _)
+        | ColorAttribute::TrueColorWithDefaultFallback(c) => {
+            let (r, g, b, _) = c.to_srgb_u8();
+            (r, g, b)
+        }
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+pub fn svg(surface: &mut Surface) -> String {
+    let (width, height) = surface.dimensions();
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="14">"#,
+        w = width as f32 * CELL_WIDTH,
+        h = height as f32 * CELL_HEIGHT,
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex(ColorAttribute::Default, DEFAULT_BG)
+    )
+    .unwrap();
+    for (y, row) in rows(surface).into_iter().enumerate() {
+        let top = y as f32 * CELL_HEIGHT;
+        // Runs of cells drawn alike, each placed at its own column. Wide
+        // characters get runs to themselves, as fonts rarely draw them at
+        // exactly two cells.
+        let mut runs: Vec<(usize, usize, CellAttributes, String)> = vec![];
+        for (x, cell) in row {
+            let width = cell.width().max(1);
+            match runs.last_mut() {
+                Some((start, end, attrs, text))
+                    if width == 1
+                        && *end - *start == text.chars().count()
+                        && attrs == cell.attrs() =>
+                {
+                    *end += 1;
+                    *text += cell.str();
+                }
+                _ => runs.push((x, x + width, cell.attrs().clone(), cell.str().to_owned())),
+            }
+        }
+        for (start, end, attrs, text) in runs {
+            let (mut fg, mut bg) = (
+                hex(attrs.foreground(), DEFAULT_FG),
+                hex(attrs.background(), DEFAULT_BG),
+            );
+            if attrs.reverse() {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+            let left = start as f32 * CELL_WIDTH;
+            if attrs.background() != ColorAttribute::Default || attrs.reverse() {
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    left,
+                    top,
+                    (end - start) as f32 * CELL_WIDTH,
+                    CELL_HEIGHT,
+                    bg
+                )
+                .unwrap();
+            }
+            if text.trim().is_empty() {
+                continue;
+            }
+            let mut style = String::new();
+            if attrs.intensity() == Intensity::Bold {
+                style += r#" font-weight="bold""#;
+            }
+            if attrs.italic() {
+                style += r#" font-style="italic""#;
+            }
+            match (attrs.underline() != Underline::None, attrs.strikethrough()) {
+                (true, true) => style += r#" text-decoration="underline line-through""#,
+                (true, false) => style += r#" text-decoration="underline""#,
+                (false, true) => style += r#" text-decoration="line-through""#,
+                (false, false) => {}
+            }
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" fill="{}"{} xml:space="preserve">{}</text>"#,
+                left,
+                top + CELL_HEIGHT * 0.8,
+                fg,
+                style,
+                escape(&text)
+            )
+            .unwrap();
+        }
+    }
+    out += "</svg>\n";
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{widgets::Paragraph, Frame};
+    use sanguine::bridge::{Bridge, BridgeInner};
+    use termwiz::surface::Change;
+
+    #[test]
+    fn ansi_of_a_paragraph() {
+        let mut surface = Surface::new(4, 2);
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                f.render_widget(Paragraph::new("ab\ncd"), f.size());
+            })
+            .unwrap();
+        assert_eq!(
+            ansi(&mut surface),
+            "\x1b[0mab  \x1b[0m\n\x1b[0mcd  \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn ansi_switches_attributes_between_runs() {
+        let mut surface = Surface::new(4, 1);
+        let mut bold = CellAttributes::default();
+        bold.set_intensity(Intensity::Bold)
+            .set_foreground(ColorAttribute::PaletteIndex(1));
+        surface.add_change(Change::AllAttributes(bold));
+        surface.add_change("ab");
+        surface.add_change(Change::AllAttributes(CellAttributes::default()));
+        surface.add_change("c");
+        assert_eq!(ansi(&mut surface), "\x1b[0;1;31mab\x1b[0mc \x1b[0m\n");
+    }
+
+    #[test]
+    fn ansi_skips_wide_character_padding() {
+        let mut surface = Surface::new(4, 1);
+        surface.add_change("日x");
+        assert_eq!(ansi(&mut surface), "\x1b[0m日x \x1b[0m\n");
+    }
+
+    #[test]
+    fn encode_picks_the_format_from_the_extension() {
+        let mut surface = Surface::new(1, 1);
+        assert!(encode(Path::new("shot.svg"), &mut surface).starts_with("<svg"));
+        assert!(encode(Path::new("shot.ansi"), &mut surface).starts_with("\x1b[0m"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Keeping a panic in one widget from taking down the whole UI.
+//!
+//! A widget that panics while its lock is held for writing poisons it, and
+//! every later `.write().unwrap()` on it panics too, all the way out of the
+//! event loop with the terminal still in raw mode. [`Supervised`] stands
+//! between sanguine and a widget: it catches the panic, stops touching the
+//! widget, and draws a placeholder in its place, while the panic hook from
+//! [`install`] writes what happened to `errors.log` instead of the screen.
+//! Panics nothing catches still reach the hook that was there before.
+
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::{Event, KeyCode, UserEvent},
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use crate::{
+    error::{self, DemoError},
+    Message,
+};
+
+/// The message of the last panic, for the placeholder to show.
+static LAST: Mutex<Option<String>> = Mutex::new(None);
+
+thread_local! {
+    /// How many [`catch`]es this thread is inside of.
+    static CATCHING: Cell<usize> = Cell::new(0);
+}
+
+/// Log panics, and keep the ones [`catch`] handles from being printed over
+/// the UI.
+pub fn install() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(s) => s.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(s) => s.clone(),
+                None => "unknown panic".to_owned(),
+            },
+        };
+        let message = match info.location() {
+            Some(at) => format!("{} at {}:{}", message, at.file(), at.line()),
+            None => message,
+        };
+        error::log(&DemoError::panicked(message.clone()));
+        if CATCHING.with(Cell::get) == 0 {
+            previous(info);
+        } else if let Ok(mut last) = LAST.lock() {
+            *last = Some(message);
+        }
+    }));
+}
+
+/// Run `f`, returning the panic message if it panics.
+pub fn catch<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    CATCHING.with(|c| c.set(c.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(c.get() - 1));
+    result.map_err(|_| {
+        LAST.lock()
+            .ok()
+            .and_then(|mut last| last.take())
+            .unwrap_or_else(|| "unknown panic".to_owned())
+    })
+}
+
+/// Whether a [`Supervised`] widget has crashed, for code that holds the
+/// widget outside of the layout too.
+#[derive(Clone)]
+pub struct Crashed(Arc<Mutex<Option<String>>>);
+
+impl Crashed {
+    pub fn get(&self) -> bool {
+        self.0.lock().map_or(true, |crash| crash.is_some())
+    }
+}
+
+/// A widget that is replaced with a placeholder once it panics.
+pub struct Supervised<W: ?Sized> {
+    name: &'static str,
+    widget: Arc<RwLock<W>>,
+    /// Why the widget crashed. Shared with the children it renders, so a
+    /// crash in one of them takes the whole widget down with it.
+    crash: Arc<Mutex<Option<String>>>,
+    /// Whether Esc closes the placeholder, for floating panels.
+    closable: bool,
+}
+
+impl<W: Widget<Message, ()>> Supervised<W> {
+    /// Supervise a floating panel.
+    pub fn new(name: &'static str, widget: W) -> Supervised<W> {
+        Supervised {
+            name,
+            widget: Arc::new(RwLock::new(widget)),
+            crash: Arc::default(),
+            closable: true,
+        }
+    }
+}
+
+impl<W: Widget<Message, ()> + ?Sized> Supervised<W> {
+    /// Supervise a widget that fills the screen and may have other handles,
+    /// which find it poisoned if it panics.
+    pub fn shared(name: &'static str, widget: Arc<RwLock<W>>) -> Supervised<W> {
+        Supervised {
+            name,
+            widget,
+            crash: Arc::default(),
+            closable: false,
+        }
+    }
+
+    /// A handle that turns true once this widget or one of its children
+    /// panics. A caught panic doesn't poison the widget's lock, so this is how
+    /// the rest of the app finds out.
+    pub fn crashed_handle(&self) -> Crashed {
+        Crashed(self.crash.clone())
+    }
+
+    fn crashed(&self) -> bool {
+        self.crash.lock().map_or(true, |crash| crash.is_some())
+    }
+
+    fn crash(&self, message: String) {
+        if let Ok(mut crash) = self.crash.lock() {
+            crash.get_or_insert(message);
+        }
+    }
+
+    /// The widget was poisoned by a panic outside of it, so the hook logged
+    /// the panic but there was nothing here to catch.
+    fn poisoned(&self) -> String {
+        let e = DemoError::poisoned(self.name);
+        error::log(&e);
+        e.to_string()
+    }
+
+    fn placeholder(&self, surface: &mut Surface) {
+        let message = self
+            .crash
+            .lock()
+            .ok()
+            .and_then(|crash| crash.clone())
+            .unwrap_or_default();
+        let mut text = format!("{}\n\nThe details are in errors.log.", message);
+        if self.closable {
+            text += " Esc closes this.";
+        }
+        let title = format!("{} crashed", self.name);
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let area = f.size();
+                f.render_widget(Clear, area);
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(Style::default().fg(Color::Red));
+                f.render_widget(
+                    Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+                    area,
+                );
+            })
+            .ok();
+    }
+}
+
+impl<W: Widget<Message, ()> + ?Sized> Widget<Message, ()> for Supervised<W> {
+    fn render<'r>(
+        &self,
+        cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        if !self.crashed() {
+            let rendered = match self.widget.read() {
+                Ok(widget) => catch(|| widget.render(cx, surface)),
+                Err(_) => Err(self.poisoned()),
+            };
+            match rendered {
+                Ok(children) => {
+                    return children.map(|children| {
+                        children
+                            .into_iter()
+                            .map(|(rect, child)| {
+                                let child: Arc<RwLock<dyn Widget<Message, ()>>> =
+                                    Arc::new(RwLock::new(Supervised {
+                                        name: self.name,
+                                        widget: child,
+                                        crash: self.crash.clone(),
+                                        closable: self.closable,
+                                    }));
+                                (rect, child)
+                            })
+                            .collect()
+                    })
+                }
+                Err(message) => self.crash(message),
+            }
+        }
+        self.placeholder(surface);
+        None
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        if !self.crashed() {
+            let updated = match self.widget.write() {
+                Ok(mut widget) => catch(|| widget.update(cx, event)),
+                Err(_) => Err(self.poisoned()),
+            };
+            return match updated {
+                Ok(result) => result,
+                Err(message) => {
+                    self.crash(message);
+                    Ok(())
+                }
+            };
+        }
+        match event {
+            Event::Key(k) if self.closable && k.key == KeyCode::Escape => {
+                cx.tx.send(UserEvent::User(Message::Close(cx.owner))).ok();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn cursor(&self) -> Option<(Option<usize>, usize, usize)> {
+        if self.crashed() {
+            return None;
+        }
+        let widget = self.widget.read().ok()?;
+        catch(|| widget.cursor()).unwrap_or_else(|message| {
+            self.crash(message);
+            None
+        })
+    }
+}
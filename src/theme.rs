@@ -0,0 +1,220 @@
+//! Colors and glyphs for everything on screen that carries meaning.
+//!
+//! No highlight relies on color alone: diff lines and diagnostic signs have
+//! glyphs, severities underline with different shapes and search matches are
+//! underlined as well as colored. Besides the default, there are palettes for
+//! the three common kinds of color blindness, built from colors that stay
+//! apart for each.
+
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use termwiz::color::{ColorAttribute, SrgbaTuple};
+
+use crate::error::{DemoError, DemoResult};
+
+pub const THEMES: &[&str] = &["default", "deuteranopia", "protanopia", "tritanopia"];
+
+/// A color from the terminal's 16-color palette or an exact `#rrggbb` one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ThemeColor {
+    Palette(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl ThemeColor {
+    const NAMES: &'static [&'static str] = &[
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "gray",
+    ];
+
+    pub fn tui(self) -> Color {
+        match self {
+            ThemeColor::Palette(n) => Color::Indexed(n),
+            ThemeColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+        }
+    }
+
+    pub fn termwiz(self) -> ColorAttribute {
+        match self {
+            ThemeColor::Palette(n) => ColorAttribute::PaletteIndex(n),
+            ThemeColor::Rgb(r, g, b) => ColorAttribute::TrueColorWithDefaultFallback(SrgbaTuple(
+                r as f32 / 255.,
+                g as f32 / 255.,
+                b as f32 / 255.,
+                1.,
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    /// `red`, `bright-red` and so on, or `#rrggbb`.
+    fn try_from(s: String) -> std::result::Result<ThemeColor, String> {
+        let invalid = || format!("invalid color `{}`", s);
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(invalid)
+            };
+            if hex.len() != 6 {
+                return Err(invalid());
+            }
+            return Ok(ThemeColor::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        let (bright, name) = match s.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, s.as_str()),
+        };
+        let name = if name == "grey" { "gray" } else { name };
+        Self::NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|n| ThemeColor::Palette(n as u8 + bright))
+            .ok_or_else(invalid)
+    }
+}
+
+/// The things a theme colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    DiffAdded,
+    DiffRemoved,
+    Error,
+    Warning,
+    Info,
+    Hint,
+    SearchMatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub color: ThemeColor,
+    /// Marks the same meaning without color, e.g. `+` on added lines.
+    pub glyph: String,
+}
+
+/// One entry of `[theme.highlights]`; unset fields keep the theme's value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightSettings {
+    pub color: Option<ThemeColor>,
+    pub glyph: Option<String>,
+}
+
+/// The `[theme]` section of the config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSettings {
+    /// One of [`THEMES`]. Defaults to `default`.
+    pub name: Option<String>,
+    pub highlights: HashMap<Role, HighlightSettings>,
+}
+
+pub struct Theme {
+    highlights: HashMap<Role, Highlight>,
+}
+
+fn rgb(hex: u32) -> ThemeColor {
+    ThemeColor::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+/// The colors of each built-in theme, in [`Role`] order.
+fn palette(name: &str) -> Option<[ThemeColor; 7]> {
+    use ThemeColor::Palette;
+    Some(match name {
+        "default" => [
+            Palette(2),
+            Palette(1),
+            Palette(1),
+            Palette(3),
+            Palette(4),
+            Palette(7),
+            Palette(3),
+        ],
+        // Red and green merge; blue against orange stays clear.
+        "deuteranopia" => [
+            rgb(0x0072b2),
+            rgb(0xe69f00),
+            rgb(0xd55e00),
+            rgb(0xf0e442),
+            rgb(0x56b4e9),
+            rgb(0xcc79a7),
+            rgb(0xf0e442),
+        ],
+        // Like deuteranopia, but reds also darken, so lean on brighter oranges.
+        "protanopia" => [
+            rgb(0x56b4e9),
+            rgb(0xffb000),
+            rgb(0xfe6100),
+            rgb(0xf0e442),
+            rgb(0x648fff),
+            rgb(0xcc79a7),
+            rgb(0xf0e442),
+        ],
+        // Blue and yellow merge; red against teal stays clear.
+        "tritanopia" => [
+            rgb(0x009e9e),
+            rgb(0xdc267f),
+            rgb(0xd00000),
+            rgb(0xff7f7f),
+            rgb(0x00b3b3),
+            rgb(0xa0a0a0),
+            rgb(0xff9fcf),
+        ],
+        _ => return None,
+    })
+}
+
+impl Theme {
+    pub fn new(settings: &ThemeSettings) -> DemoResult<Theme> {
+        let name = settings.name.as_deref().unwrap_or("default");
+        let colors = palette(name).ok_or_else(|| {
+            DemoError::config(format!(
+                "unknown theme `{}`, expected one of {}",
+                name,
+                THEMES.join(", ")
+            ))
+        })?;
+        let roles = [
+            (Role::DiffAdded, "+"),
+            (Role::DiffRemoved, "-"),
+            (Role::Error, "E"),
+            (Role::Warning, "W"),
+            (Role::Info, "I"),
+            (Role::Hint, "H"),
+            (Role::SearchMatch, ""),
+        ];
+        let mut highlights: HashMap<Role, Highlight> = roles
+            .into_iter()
+            .zip(colors)
+            .map(|((role, glyph), color)| {
+                let glyph = glyph.to_owned();
+                (role, Highlight { color, glyph })
+            })
+            .collect();
+        for (role, custom) in &settings.highlights {
+            let highlight = highlights.get_mut(role).unwrap();
+            if let Some(color) = custom.color {
+                highlight.color = color;
+            }
+            if let Some(glyph) = &custom.glyph {
+                highlight.glyph = glyph.clone();
+            }
+        }
+        Ok(Theme { highlights })
+    }
+
+    pub fn get(&self, role: Role) -> &Highlight {
+        &self.highlights[&role]
+    }
+
+    /// The glyph of `role` for a single cell, such as the sign column.
+    pub fn sign(&self, role: Role) -> char {
+        self.get(role).glyph.chars().next().unwrap_or(' ')
+    }
+}
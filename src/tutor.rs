@@ -0,0 +1,254 @@
+//! A guided tour of the editor, like vimtutor.
+//!
+//! Each [`Lesson`] loads a short exercise into a scratch buffer and explains
+//! above it what to do. After every key the tutor looks at the buffer, and
+//! once the exercise is done moves on to the next lesson. Instructions name
+//! actions rather than keys, so they follow the user's own keymap.
+
+use ratatui::{
+    layout::Rect as Area,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use sanguine::{
+    bridge::{Bridge, BridgeInner},
+    event::Event,
+    layout::Rect,
+    surface::Surface,
+    RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    error::{Context, DemoResult, Poisoned},
+    keymap::{Action, Keymap},
+    Message, MiniEditor,
+};
+
+/// Rows above the editor for the instructions.
+const PANEL: u16 = 8;
+
+/// What a lesson's check can see of the editor.
+pub struct Exercise {
+    pub lines: Vec<String>,
+    /// Column and line of the cursor.
+    pub cursor: (usize, usize),
+    pub modified: bool,
+    pub search: String,
+    pub wrap: bool,
+}
+
+pub struct Lesson {
+    title: &'static str,
+    /// What to do. `{save}` and the like stand for the keys bound to an
+    /// action.
+    steps: &'static str,
+    /// The buffer the lesson starts from.
+    text: &'static str,
+    done: fn(&Exercise) -> bool,
+}
+
+/// The placeholders lessons may use, and the actions they stand for.
+const PLACEHOLDERS: &[(&str, Action)] = &[
+    ("{save}", Action::Save),
+    ("{search}", Action::Search),
+    ("{setlocal}", Action::SetLocal),
+];
+
+fn line_is(exercise: &Exercise, line: usize, text: &str) -> bool {
+    exercise.lines.get(line).map(|l| l.trim_end()) == Some(text)
+}
+
+pub const LESSONS: &[Lesson] = &[
+    Lesson {
+        title: "Moving the cursor",
+        steps: "The arrow keys move the cursor, and Home and End jump to the \
+                start and end of a line. Move the cursor onto the X below.",
+        text: "The letter to find is further down.\n\n\n        find me -> X <- here\n",
+        done: |e| {
+            let (x, y) = e.cursor;
+            e.lines.get(y).and_then(|l| l.chars().nth(x)) == Some('X')
+        },
+    },
+    Lesson {
+        title: "Typing",
+        steps: "Anything you type goes into the buffer at the cursor. Make the \
+                first line read: The quick brown fox jumps over the lazy dog.",
+        text: "The quick fox jumps over the dog.\n",
+        done: |e| line_is(e, 0, "The quick brown fox jumps over the lazy dog."),
+    },
+    Lesson {
+        title: "Deleting",
+        steps: "Backspace deletes before the cursor and Delete after it. Remove \
+                the doubled words so the first line reads: The cat sat on the mat.",
+        text: "The the cat sat on on the mat.\n",
+        done: |e| line_is(e, 0, "The cat sat on the mat."),
+    },
+    Lesson {
+        title: "Searching",
+        steps: "{search} opens the search prompt at the bottom. Search for the \
+                word needle and press Enter; every match is highlighted.",
+        text: "hay hay hay hay hay\nhay hay needle hay\nhay hay hay hay hay\n",
+        done: |e| e.search.eq_ignore_ascii_case("needle"),
+    },
+    Lesson {
+        title: "Saving",
+        steps: "A tab's buffer is only written to its file when you save. Change \
+                draft to final on the first line, then save with {save}.",
+        text: "This is the draft version.\n",
+        done: |e| line_is(e, 0, "This is the final version.") && !e.modified,
+    },
+    Lesson {
+        title: "Buffer options",
+        steps: "{setlocal} changes options for this buffer only, without touching \
+                your config. Type wrap at the prompt and press Enter, so the long \
+                line below wraps.",
+        text: "This line goes on and on, well past the edge of the window, so that \
+               the end of it can only be read once lines wrap around to the next \
+               row instead of being cut off at the border.\n",
+        done: |e| e.wrap,
+    },
+];
+
+pub struct Tutor {
+    editor: Arc<RwLock<MiniEditor>>,
+    keymap: Arc<Keymap>,
+    /// The scratch file the exercises are loaded into.
+    file: PathBuf,
+    lesson: usize,
+}
+
+impl Tutor {
+    pub fn new(
+        editor: Arc<RwLock<MiniEditor>>,
+        keymap: Arc<Keymap>,
+        file: PathBuf,
+    ) -> DemoResult<Tutor> {
+        let tutor = Tutor {
+            editor,
+            keymap,
+            file,
+            lesson: 0,
+        };
+        tutor.start()?;
+        Ok(tutor)
+    }
+
+    /// Load the current lesson's exercise.
+    fn start(&self) -> DemoResult<()> {
+        let Some(lesson) = LESSONS.get(self.lesson) else {
+            return Ok(());
+        };
+        std::fs::write(&self.file, lesson.text)
+            .context(format!("writing {}", self.file.display()))?;
+        self.editor
+            .write()
+            .or_poisoned("editor")?
+            .start_exercise(self.file.clone())
+    }
+
+    /// `steps` with each placeholder replaced by the keys for its action.
+    fn steps(&self, steps: &str) -> String {
+        let mut steps = steps.to_owned();
+        for (placeholder, action) in PLACEHOLDERS {
+            steps = steps.replace(placeholder, &self.keymap.keys_for(*action));
+        }
+        steps
+    }
+}
+
+impl Widget<Message, ()> for Tutor {
+    fn render<'r>(
+        &self,
+        _cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let (title, steps) = match LESSONS.get(self.lesson) {
+            Some(lesson) => (
+                format!(
+                    "Lesson {}/{}: {}",
+                    self.lesson + 1,
+                    LESSONS.len(),
+                    lesson.title
+                ),
+                self.steps(lesson.steps),
+            ),
+            None => (
+                "Tutorial finished".to_owned(),
+                "That's the tour. The `keymap dump` subcommand lists every \
+                 binding, and the buffer below is yours to keep practising in."
+                    .to_owned(),
+            ),
+        };
+        let (width, height) = surface.dimensions();
+        surface
+            .ratatui()
+            .draw(|f: &mut Frame<BridgeInner>| {
+                let area = f.size();
+                let block = Block::default().borders(Borders::ALL).title(Span::styled(
+                    title,
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+                let text = vec![
+                    Spans::from(steps),
+                    Spans::default(),
+                    Spans::from(Span::styled(
+                        "the lesson moves on by itself once the exercise is done",
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ];
+                f.render_widget(
+                    Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+                    Area {
+                        height: PANEL.min(area.height),
+                        ..area
+                    },
+                );
+            })
+            .unwrap();
+        let panel = (PANEL as usize).min(height);
+        let editor: Arc<RwLock<dyn Widget<Message, ()>>> = self.editor.clone();
+        Some(vec![(
+            Rect {
+                x: 0.,
+                y: panel as f32,
+                width: width as f32,
+                height: (height - panel) as f32,
+            },
+            editor,
+        )])
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        let exercise = {
+            let mut editor = self.editor.write().or_poisoned("editor")?;
+            editor.update(cx, event)?;
+            editor.exercise()
+        };
+        let Some(lesson) = LESSONS.get(self.lesson) else {
+            return Ok(());
+        };
+        if exercise.map_or(false, |e| (lesson.done)(&e)) {
+            self.lesson += 1;
+            self.start()?;
+            self.editor.write().or_poisoned("editor")?.status =
+                Some(format!("lesson {} done", self.lesson));
+        }
+        Ok(())
+    }
+
+    fn cursor(&self) -> Option<(Option<usize>, usize, usize)> {
+        // One level deeper than the editor's own.
+        let (depth, x, y) = self.editor.read().ok()?.cursor()?;
+        Some((depth.map(|d| d + 1), x, y))
+    }
+}
@@ -0,0 +1,249 @@
+use sanguine::{
+    event::Event, layout::Rect, surface::Surface, widgets::TextBox, RenderCtx, UpdateCtx, Widget,
+};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+use termwiz::{
+    cell::{Cell, CellAttributes, Intensity, Underline},
+    color::AnsiColor,
+};
+
+use crate::{
+    bidi,
+    diagnostics::{Diagnostic, Diagnostics},
+    error::Poisoned,
+    search::Search,
+    theme::{Role, Theme},
+    Message,
+};
+
+/// Width of the sign column to the left of the text.
+pub const GUTTER: usize = 2;
+
+/// How many screen rows a line `len` characters long takes when wrapped at
+/// `width`. There is always room after the last character for the cursor.
+pub fn wrapped_rows(len: usize, width: usize) -> usize {
+    len / width.max(1) + 1
+}
+
+/// Where column `x` of line `y` is drawn when `lines` wrap at `width`.
+pub fn wrap_position(lines: &[String], width: usize, x: usize, y: usize) -> (usize, usize) {
+    let width = width.max(1);
+    let row: usize = lines
+        .iter()
+        .take(y)
+        .map(|line| wrapped_rows(line.chars().count(), width))
+        .sum();
+    (x % width, row + x / width)
+}
+
+/// Draws a buffer's TextBox with decorations layered over it.
+///
+/// The TextBox renders into its own surface, which is then marked up cell by
+/// cell and copied next to the sign column. TextBox doesn't scroll, so buffer
+/// line `n` is always screen row `n`, unless lines wrap. Then the TextBox draws
+/// into a surface as wide as the longest line, which is folded into the view.
+pub struct EditorView {
+    pub editor: Arc<RwLock<TextBox>>,
+    pub file: PathBuf,
+    pub diagnostics: Arc<RwLock<Diagnostics>>,
+    pub virtual_text: bool,
+    pub wrap: bool,
+    pub search: Arc<RwLock<Search>>,
+    pub theme: Arc<Theme>,
+    /// Composition in progress, drawn at the cursor.
+    pub preedit: String,
+}
+
+impl EditorView {
+    fn underline(&self, rows: &mut [&mut [Cell]], lines: &[String], d: &Diagnostic) {
+        for line in d.start.0..=d.end.0 {
+            let Some(row) = rows.get_mut(line) else {
+                break;
+            };
+            let len = lines.get(line).map(|l| l.chars().count()).unwrap_or(0);
+            let from = if line == d.start.0 { d.start.1 } else { 0 };
+            let to = if line == d.end.0 { d.end.1 } else { len };
+            // Always mark at least one cell, even for zero-width ranges.
+            let to = to.max(from + 1).min(row.len());
+            for cell in row.iter_mut().take(to).skip(from) {
+                cell.attrs_mut()
+                    .set_underline(d.severity.underline())
+                    .set_underline_color(self.theme.get(d.severity.role()).color.termwiz());
+            }
+        }
+    }
+
+    fn highlight_matches(&self, rows: &mut [&mut [Cell]], lines: &[String], search: &Search) {
+        let len = search.match_len();
+        let color = self.theme.get(Role::SearchMatch).color.termwiz();
+        for (row, line) in rows.iter_mut().zip(lines) {
+            for col in search.matches(line) {
+                for cell in row.iter_mut().skip(col).take(len) {
+                    cell.attrs_mut()
+                        .set_background(color)
+                        .set_foreground(AnsiColor::Black)
+                        .set_intensity(Intensity::Bold)
+                        // Single is taken by hints, and the others by
+                        // diagnostics of other severities.
+                        .set_underline(Underline::Double);
+                }
+            }
+        }
+    }
+
+    fn virtual_text(&self, row: &mut [Cell], line: &str, d: &Diagnostic) {
+        let mut attrs = CellAttributes::default();
+        attrs
+            .set_foreground(self.theme.get(d.severity.role()).color.termwiz())
+            .set_italic(true);
+        let start = line.chars().count() + 2;
+        for (cell, c) in row.iter_mut().skip(start).zip(d.message.chars()) {
+            *cell = Cell::new(c, attrs.clone());
+        }
+    }
+}
+
+impl Widget<Message, ()> for EditorView {
+    fn render<'r>(
+        &self,
+        cx: &RenderCtx<'r, Message, ()>,
+        surface: &mut Surface,
+    ) -> Option<Vec<(Rect, Arc<RwLock<dyn Widget<Message, ()>>>)>> {
+        let (width, height) = surface.dimensions();
+        // A lock poisoned by a panic stays that way; skip drawing rather than
+        // panic again every frame.
+        let Ok(editor) = self.editor.read() else {
+            return None;
+        };
+        let Ok(lines) = editor.buffer().read().map(|lines| lines.clone()) else {
+            return None;
+        };
+        let text_width = width.saturating_sub(GUTTER).max(1);
+        let folded_width = if self.wrap {
+            let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            wrapped_rows(longest, text_width) * text_width
+        } else {
+            text_width
+        };
+        let mut text = Surface::new(folded_width, height);
+        let children = editor.render(cx, &mut text);
+
+        let Ok(diagnostics) = self.diagnostics.read() else {
+            return None;
+        };
+        let diagnostics = diagnostics.for_file(&self.file);
+        // The most severe diagnostic on each line picks the sign and virtual text.
+        let mut worst: BTreeMap<usize, &Diagnostic> = BTreeMap::new();
+        for d in diagnostics {
+            let entry = worst.entry(d.start.0).or_insert(d);
+            if d.severity < entry.severity {
+                *entry = d;
+            }
+        }
+
+        {
+            let mut rows = text.screen_cells();
+            if let Ok(search) = self.search.read() {
+                self.highlight_matches(&mut rows, &lines, &search);
+            }
+            for d in diagnostics {
+                self.underline(&mut rows, &lines, d);
+            }
+            // Reorder after marking up, so highlights travel with their text.
+            for (row, line) in rows.iter_mut().zip(&lines) {
+                if let Some(order) = bidi::visual_order(line) {
+                    let logical: Vec<Cell> = row.iter().take(order.len()).cloned().collect();
+                    for (cell, &l) in row.iter_mut().zip(&order) {
+                        if let Some(from) = logical.get(l) {
+                            *cell = from.clone();
+                        }
+                    }
+                }
+            }
+            if let Some((_, x, y)) = <TextBox as Widget<Message, ()>>::cursor(&editor) {
+                if let (Some(row), Some(line)) = (rows.get_mut(y), lines.get(y)) {
+                    let x = bidi::to_visual(line, x);
+                    for (cell, c) in row.iter_mut().skip(x).zip(self.preedit.chars()) {
+                        let mut attrs = CellAttributes::default();
+                        attrs.set_underline(Underline::Single).set_reverse(true);
+                        *cell = Cell::new(c, attrs);
+                    }
+                }
+            }
+            if self.virtual_text {
+                for (&line, d) in &worst {
+                    if let (Some(row), Some(line)) = (rows.get_mut(line), lines.get(line)) {
+                        self.virtual_text(row, line, d);
+                    }
+                }
+            }
+        }
+
+        // The screen row each line starts on.
+        let starts: Vec<usize> = if self.wrap {
+            lines
+                .iter()
+                .scan(0, |at, line| {
+                    let start = *at;
+                    *at += wrapped_rows(line.chars().count(), text_width);
+                    Some(start)
+                })
+                .collect()
+        } else {
+            (0..lines.len()).collect()
+        };
+        let mut rows = surface.screen_cells();
+        for (&line, d) in &worst {
+            let row = starts.get(line).and_then(|&start| rows.get_mut(start));
+            if let Some(cell) = row.and_then(|row| row.first_mut()) {
+                let role = d.severity.role();
+                let mut attrs = CellAttributes::default();
+                attrs.set_foreground(self.theme.get(role).color.termwiz());
+                *cell = Cell::new(self.theme.sign(role), attrs);
+            }
+        }
+        if self.wrap {
+            let from = text.screen_cells();
+            for ((line, cells), &start) in lines.iter().zip(from.iter()).zip(&starts) {
+                let chunks = cells.chunks(text_width);
+                let count = wrapped_rows(line.chars().count(), text_width);
+                for (row, chunk) in rows.iter_mut().skip(start).zip(chunks.take(count)) {
+                    for (cell, from) in row.iter_mut().skip(GUTTER).zip(chunk) {
+                        *cell = from.clone();
+                    }
+                }
+            }
+            // TextBox lays its children out for unwrapped lines.
+            return None;
+        }
+        drop(rows);
+        surface.draw_from_screen(&text, GUTTER, 0);
+
+        children.map(|children| {
+            children
+                .into_iter()
+                .map(|(rect, widget)| {
+                    (
+                        Rect {
+                            x: rect.x + GUTTER as f32,
+                            ..rect
+                        },
+                        widget,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    fn update<'u>(
+        &mut self,
+        cx: &mut UpdateCtx<'u, Message, ()>,
+        event: Event<Message>,
+    ) -> sanguine::error::Result<()> {
+        self.editor.write().or_poisoned("editor")?.update(cx, event)
+    }
+}